@@ -0,0 +1,197 @@
+use derive_more::Constructor;
+use itertools::Itertools;
+
+use crate::{
+    extract::EXCLUDED_CATEGORY,
+    line::{Line, Lines},
+    stats::median,
+};
+
+/// Robust z-score above which a value is an outlier (Iglewicz and Hoaglin).
+const OUTLIER_SCORE: f32 = 3.5;
+/// Minimum values count to consider a history as representative.
+const MIN_HISTORY: usize = 5;
+
+/// Category month total far from its usual monthly amount.
+#[derive(Debug, Clone, PartialEq, Constructor)]
+pub struct MonthAnomaly {
+    category: String,
+    month: chrono::NaiveDate,
+    amount: f32,
+    median: f32,
+    score: f32,
+}
+
+impl MonthAnomaly {
+    pub fn category(&self) -> &str {
+        &self.category
+    }
+
+    pub fn month(&self) -> chrono::NaiveDate {
+        self.month
+    }
+
+    pub fn amount(&self) -> f32 {
+        self.amount
+    }
+
+    pub fn median(&self) -> f32 {
+        self.median
+    }
+
+    pub fn score(&self) -> f32 {
+        self.score
+    }
+}
+
+/// Line amount far from usual amounts of its sub-category.
+#[derive(Debug, Clone, PartialEq, Constructor)]
+pub struct LineAnomaly {
+    line: Line,
+    median: f32,
+    score: f32,
+}
+
+impl LineAnomaly {
+    pub fn line(&self) -> &Line {
+        &self.line
+    }
+
+    pub fn median(&self) -> f32 {
+        self.median
+    }
+
+    pub fn score(&self) -> f32 {
+        self.score
+    }
+}
+
+/// Median and robust z-score (based on median absolute deviation) of each value.
+/// Scores are `None` when values are too few or mostly identical.
+pub fn robust_z_scores(values: &[f32]) -> (f32, Vec<Option<f32>>) {
+    if values.len() < MIN_HISTORY {
+        return (0.0, vec![None; values.len()]);
+    }
+
+    let median_ = median(values);
+    let deviations = values
+        .iter()
+        .map(|v| (v - median_).abs())
+        .collect::<Vec<f32>>();
+    let mad = median(&deviations);
+    let scores = values
+        .iter()
+        .map(|v| (mad > 0.0).then(|| 0.6745 * (v - median_) / mad))
+        .collect();
+
+    (median_, scores)
+}
+
+/// Months where a category total is an outlier of its monthly history
+pub fn month_anomalies(lines: &Lines) -> Vec<MonthAnomaly> {
+    let mut anomalies = vec![];
+
+    for (category, months, _, _) in lines.categories_totals() {
+        if category == EXCLUDED_CATEGORY {
+            continue;
+        }
+
+        let (median_, scores) = robust_z_scores(months);
+        for ((month, amount), score) in lines.all_months().iter().zip(months).zip(scores) {
+            if let Some(score) = score
+                && score.abs() > OUTLIER_SCORE
+            {
+                anomalies.push(MonthAnomaly::new(
+                    category.clone(),
+                    *month,
+                    *amount,
+                    median_,
+                    score,
+                ));
+            }
+        }
+    }
+
+    anomalies
+        .into_iter()
+        .sorted_by(|a, b| b.score().abs().total_cmp(&a.score().abs()))
+        .collect()
+}
+
+/// Expenses which are outliers among expenses of their sub-category
+pub fn line_anomalies(lines: &Lines) -> Vec<LineAnomaly> {
+    let mut anomalies = vec![];
+
+    for (category, sub_category) in lines.sub_categories() {
+        if category == EXCLUDED_CATEGORY {
+            continue;
+        }
+
+        let expenses = lines
+            .lines()
+            .iter()
+            .filter(|l| {
+                l.categorie() == category && l.sous_categorie() == sub_category && l.amount() < 0.0
+            })
+            .collect::<Vec<&Line>>();
+        let (median_, scores) =
+            robust_z_scores(&expenses.iter().map(|l| l.amount()).collect::<Vec<f32>>());
+        for (line, score) in expenses.into_iter().zip(scores) {
+            // Only larger expenses than usual are unusual
+            if let Some(score) = score
+                && score < -OUTLIER_SCORE
+            {
+                anomalies.push(LineAnomaly::new(line.clone(), median_, score));
+            }
+        }
+    }
+
+    anomalies
+        .into_iter()
+        .sorted_by(|a, b| a.score().total_cmp(&b.score()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extract::TryIntoLines;
+
+    #[test]
+    fn detect_unusual_expenses() {
+        // Given
+        let mut raw = "header".to_string();
+        for (month, amount) in
+            (1..=8).zip(["-100", "-110", "-95", "-105", "-300", "-100", "-98", "-102"])
+        {
+            raw.push_str(&format!(
+                "\n10/{month:02}/2024;SUPER;CB SUPER;;;Carte bancaire;Alimentation;Hyper/supermarche;{amount};;10/{month:02}/2024;10/{month:02}/2024;0"
+            ));
+        }
+        let lines = raw.into_lines("test".to_string()).unwrap();
+
+        // When
+        let months = month_anomalies(&lines);
+        let lines_ = line_anomalies(&lines);
+
+        // Then
+        assert_eq!(months.len(), 1);
+        assert_eq!(
+            months[0].month(),
+            chrono::NaiveDate::from_ymd_opt(2024, 5, 1).unwrap()
+        );
+        assert_eq!(months[0].amount(), -300.0);
+        assert_eq!(months[0].median(), -101.0);
+        assert_eq!(lines_.len(), 1);
+        assert_eq!(lines_[0].line().date_raw(), "10/05/2024");
+    }
+
+    #[test]
+    fn no_score_for_short_or_flat_history() {
+        assert_eq!(robust_z_scores(&[1.0, 2.0]).1, vec![None, None]);
+        assert_eq!(
+            robust_z_scores(&[5.0; 6]).1.into_iter().flatten().count(),
+            0
+        );
+    }
+}
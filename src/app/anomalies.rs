@@ -0,0 +1,74 @@
+use eframe::egui::{self, RichText};
+
+use crate::line::Lines;
+
+use super::effect::Effect;
+
+pub fn render(ui: &mut egui::Ui, lines: &Lines) -> Vec<Effect> {
+    let mut effects = vec![];
+
+    ui.heading("Mois inhabituels");
+    egui::Grid::new("month_anomalies")
+        .striped(true)
+        .show(ui, |ui| {
+            ui.label("");
+            ui.label(RichText::new("Categorie").strong());
+            ui.label(RichText::new("Mois").strong());
+            ui.label(RichText::new("Montant").strong());
+            ui.label(RichText::new("Médiane").strong());
+            ui.label(RichText::new("Score").strong());
+            ui.end_row();
+
+            for anomaly in lines.month_anomalies() {
+                if ui.button("👓").clicked() {
+                    effects.push(Effect::ShowLines(
+                        Some(anomaly.category().to_string()),
+                        None,
+                        anomaly.month().format("/%m/%Y").to_string(),
+                    ));
+                }
+                ui.label(anomaly.category());
+                ui.label(anomaly.month().format("%Y-%m").to_string());
+                ui.label(format!("{:>.2}", anomaly.amount()));
+                ui.label(format!("{:>.2}", anomaly.median()));
+                ui.label(format!("{:>.1}", anomaly.score()));
+                ui.end_row();
+            }
+        });
+
+    ui.separator();
+
+    ui.heading("Opérations inhabituelles");
+    egui::Grid::new("line_anomalies")
+        .striped(true)
+        .show(ui, |ui| {
+            ui.label("");
+            ui.label(RichText::new("Date").strong());
+            ui.label(RichText::new("Libellé").strong());
+            ui.label(RichText::new("Sous catégorie").strong());
+            ui.label(RichText::new("Montant").strong());
+            ui.label(RichText::new("Médiane").strong());
+            ui.label(RichText::new("Score").strong());
+            ui.end_row();
+
+            for anomaly in lines.line_anomalies() {
+                let line = anomaly.line();
+                if ui.button("👓").clicked() {
+                    effects.push(Effect::ShowLines(
+                        Some(line.categorie().to_string()),
+                        Some(line.sous_categorie().to_string()),
+                        line.date_raw().to_string(),
+                    ));
+                }
+                ui.label(line.date_raw());
+                ui.label(line.libelle_operation());
+                ui.label(line.sous_categorie());
+                ui.label(format!("{:>.2}", line.amount()));
+                ui.label(format!("{:>.2}", anomaly.median()));
+                ui.label(format!("{:>.1}", anomaly.score()));
+                ui.end_row();
+            }
+        });
+
+    effects
+}
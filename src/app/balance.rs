@@ -0,0 +1,142 @@
+use eframe::egui::{self, RichText};
+use egui_plot::{HLine, Legend, Line, Plot, PlotPoints, Points};
+
+use crate::line::Lines;
+
+use super::effect::Effect;
+
+pub fn render(ui: &mut egui::Ui, lines: &Lines) -> Vec<Effect> {
+    let mut effects = vec![];
+
+    ui.horizontal(|ui| {
+        let mut known = lines.parameters.balance.is_some();
+        let mut balance = lines.parameters.balance.unwrap_or_default();
+        let mut changed = ui
+            .checkbox(
+                &mut known,
+                format!(
+                    "Solde au {}",
+                    lines
+                        .all_lines()
+                        .iter()
+                        .map(|l| l.date())
+                        .max()
+                        .unwrap_or(lines.higher_date())
+                        .format("%d/%m/%Y")
+                ),
+            )
+            .changed();
+        changed |= ui
+            .add_enabled(known, egui::DragValue::new(&mut balance).speed(10.0))
+            .changed();
+        if changed {
+            effects.push(Effect::SetBalance(known.then_some(balance)));
+        }
+    });
+
+    let mut overdraft_threshold = lines.parameters.overdraft_threshold;
+    ui.horizontal(|ui| {
+        ui.label("Seuil d'alerte");
+        if ui
+            .add(egui::DragValue::new(&mut overdraft_threshold).speed(10.0))
+            .changed()
+        {
+            effects.push(Effect::SetOverdraftThreshold(overdraft_threshold));
+        }
+    });
+
+    if lines.parameters.balance.is_none() {
+        ui.label("Renseigner le solde pour prévoir les risques de découvert");
+        return effects;
+    }
+
+    ui.separator();
+
+    let first_date = lines.lower_date();
+    let x = |date: chrono::NaiveDate| (date - first_date).num_days() as f64;
+    let timeline = lines.balance_timeline();
+    Plot::new("balance_timeline")
+        .legend(Legend::default())
+        .height(240.0)
+        .x_axis_formatter(move |mark, _| {
+            (first_date + chrono::Days::new(mark.value.max(0.0) as u64))
+                .format("%d/%m/%Y")
+                .to_string()
+        })
+        .show(ui, |plot_ui| {
+            plot_ui.line(Line::new(
+                "Solde",
+                PlotPoints::from(
+                    timeline
+                        .iter()
+                        .filter(|e| !e.expected())
+                        .map(|e| [x(e.date()), e.balance() as f64])
+                        .collect::<Vec<[f64; 2]>>(),
+                ),
+            ));
+            plot_ui.line(
+                Line::new(
+                    "Solde prévu",
+                    PlotPoints::from(
+                        timeline
+                            .iter()
+                            .filter(|e| e.expected())
+                            .map(|e| [x(e.date()), e.balance() as f64])
+                            .collect::<Vec<[f64; 2]>>(),
+                    ),
+                )
+                .style(egui_plot::LineStyle::dashed_dense()),
+            );
+            plot_ui.hline(
+                HLine::new(
+                    "Seuil d'alerte",
+                    lines.parameters.overdraft_threshold as f64,
+                )
+                .color(egui::Color32::RED),
+            );
+            plot_ui.points(
+                Points::new(
+                    "Risque de découvert",
+                    PlotPoints::from(
+                        lines
+                            .overdraft_risks()
+                            .map(|e| [x(e.date()), e.balance() as f64])
+                            .collect::<Vec<[f64; 2]>>(),
+                    ),
+                )
+                .color(egui::Color32::RED)
+                .radius(4.0),
+            );
+        });
+
+    ui.separator();
+
+    egui::ScrollArea::vertical().show(ui, |ui| {
+        egui::Grid::new("expected_movements")
+            .striped(true)
+            .show(ui, |ui| {
+                ui.label(RichText::new("Date").strong());
+                ui.label(RichText::new("Bénéficiaire").strong());
+                ui.label(RichText::new("Montant").strong());
+                ui.label(RichText::new("Solde").strong());
+                ui.end_row();
+
+                for event in timeline.iter().filter(|e| e.expected()) {
+                    let color = if event.is_overdraft_risk(lines.parameters.overdraft_threshold) {
+                        ui.visuals().error_fg_color
+                    } else {
+                        ui.visuals().text_color()
+                    };
+                    ui.label(
+                        RichText::new(event.date().format("%d/%m/%Y").to_string()).color(color),
+                    );
+                    ui.label(RichText::new(event.payee()).color(color));
+                    ui.label(RichText::new(format!("{:>.2}", event.amount())).color(color));
+                    ui.label(RichText::new(format!("{:>.2}", event.balance())).color(color));
+                    ui.end_row();
+                }
+            });
+    });
+
+    effects
+}
@@ -0,0 +1,139 @@
+use std::f32::consts::{FRAC_PI_2, TAU};
+
+use eframe::egui::{self, Color32, RichText, Sense, Vec2, ecolor::Hsva};
+
+use crate::{
+    breakdown::{Share, category_shares, sub_category_shares},
+    line::Lines,
+};
+
+use super::effect::Effect;
+
+const OUTER_RADIUS: f32 = 140.0;
+const INNER_RADIUS: f32 = 80.0;
+/// Angle of a donut mesh segment, in radians
+const SEGMENT_ANGLE: f32 = 0.05;
+
+pub fn render(ui: &mut egui::Ui, lines: &Lines) -> Vec<Effect> {
+    let mut effects = vec![];
+
+    let category_id = ui.id().with("breakdown_category");
+    let mut category = ui.data(|d| d.get_temp::<String>(category_id));
+
+    ui.horizontal(|ui| {
+        if ui
+            .selectable_label(category.is_none(), "Toutes les catégories")
+            .clicked()
+        {
+            category = None;
+        }
+        if let Some(category) = &category {
+            ui.label("›");
+            ui.label(RichText::new(category).strong());
+        }
+    });
+    ui.separator();
+
+    let shares = match &category {
+        Some(category) => sub_category_shares(lines, category),
+        None => category_shares(lines),
+    };
+
+    ui.horizontal_top(|ui| {
+        let clicked = donut(ui, &shares);
+
+        egui::Grid::new("breakdown").striped(true).show(ui, |ui| {
+            for (i, share) in shares.iter().enumerate() {
+                ui.label(RichText::new("⏺").color(color(i)));
+                ui.label(RichText::new(share.label()).strong());
+                ui.label(format!("{:>.2}", share.amount()));
+                ui.label(format!("{:>.1}%", share.ratio() * 100.0));
+                ui.end_row();
+            }
+        });
+
+        if let Some(share) = clicked {
+            match &category {
+                Some(category) => effects.push(Effect::ShowLines(
+                    Some(category.clone()),
+                    Some(share.label().to_string()),
+                    String::new(),
+                )),
+                None => category = Some(share.label().to_string()),
+            }
+        }
+    });
+
+    ui.data_mut(|d| match category {
+        Some(category) => d.insert_temp(category_id, category),
+        None => d.remove::<String>(category_id),
+    });
+
+    effects
+}
+
+/// Draw shares as a donut, returning the clicked one
+fn donut<'a>(ui: &mut egui::Ui, shares: &'a [Share]) -> Option<&'a Share> {
+    let (rect, response) = ui.allocate_exact_size(Vec2::splat(2.0 * OUTER_RADIUS), Sense::click());
+    let center = rect.center();
+
+    // Slices start at the top and go clockwise
+    let point = |angle: f32, radius: f32| center + radius * Vec2::angled(angle - FRAC_PI_2);
+    let hovered = response.hover_pos().and_then(|pos| {
+        let offset = pos - center;
+        if !(INNER_RADIUS..=OUTER_RADIUS).contains(&offset.length()) {
+            return None;
+        }
+        let angle = (offset.angle() + FRAC_PI_2).rem_euclid(TAU);
+        let mut start = 0.0;
+        shares.iter().position(|share| {
+            start += share.ratio() * TAU;
+            angle < start
+        })
+    });
+
+    let mut start = 0.0;
+    for (i, share) in shares.iter().enumerate() {
+        let end = start + share.ratio() * TAU;
+        let segments = ((end - start) / SEGMENT_ANGLE).ceil().max(1.0) as usize;
+        let color = if hovered == Some(i) {
+            color(i).gamma_multiply(0.7)
+        } else {
+            color(i)
+        };
+
+        let mut mesh = egui::Mesh::default();
+        for segment in 0..=segments {
+            let angle = start + (end - start) * segment as f32 / segments as f32;
+            mesh.colored_vertex(point(angle, INNER_RADIUS), color);
+            mesh.colored_vertex(point(angle, OUTER_RADIUS), color);
+        }
+        for segment in 0..segments as u32 {
+            let index = 2 * segment;
+            mesh.add_triangle(index, index + 1, index + 2);
+            mesh.add_triangle(index + 1, index + 3, index + 2);
+        }
+        ui.painter().add(mesh);
+
+        start = end;
+    }
+
+    let hovered = hovered.map(|i| &shares[i]);
+    if let Some(share) = hovered {
+        response.clone().on_hover_text(format!(
+            "{}\n{:>.2} ({:>.1}%)",
+            share.label(),
+            share.amount(),
+            share.ratio() * 100.0
+        ));
+    }
+
+    hovered.filter(|_| response.clicked())
+}
+
+/// Distinct color of the given slice
+pub fn color(index: usize) -> Color32 {
+    // Golden ratio hue steps, as egui_plot automatic colors
+    let hue = (index as f32 * 0.618_034).fract();
+    Hsva::new(hue, 0.85, 0.5, 1.0).into()
+}
@@ -0,0 +1,292 @@
+use eframe::egui::{self, RichText};
+
+use crate::{
+    budget::{Budget, envelope_balances, months_income},
+    line::Lines,
+};
+
+use super::{effect::Effect, settings::Settings};
+
+pub fn render(ui: &mut egui::Ui, lines: Option<&Lines>, settings: &Settings) -> Vec<Effect> {
+    let mut effects = vec![];
+
+    let mut envelope_mode = settings.envelope_mode;
+    if ui
+        .checkbox(&mut envelope_mode, "Mode enveloppes (report des restes)")
+        .changed()
+    {
+        effects.push(Effect::SetEnvelopeMode(envelope_mode));
+    }
+
+    egui::Grid::new("budgets").striped(true).show(ui, |ui| {
+        ui.label(RichText::new("Categorie").strong());
+        ui.label(RichText::new("Sous catégorie").strong());
+        ui.label(RichText::new("Budget mensuel").strong());
+        if settings.envelope_mode {
+            ui.label(RichText::new("Dépassement couvert par").strong());
+        }
+        ui.label("");
+        ui.end_row();
+
+        for budget in &settings.budgets {
+            ui.label(budget.category());
+            ui.label(budget.sub_category().unwrap_or("Toutes"));
+            let mut amount = budget.amount();
+            if ui
+                .add(egui::DragValue::new(&mut amount).speed(10.0))
+                .changed()
+            {
+                effects.push(Effect::SetBudget(
+                    budget.category().to_string(),
+                    budget.sub_category().map(|s| s.to_string()),
+                    Some(amount),
+                ));
+            }
+            if settings.envelope_mode {
+                let covered_by = budget.covered_by();
+                egui::ComboBox::from_id_salt((
+                    "budget_cover",
+                    budget.category(),
+                    budget.sub_category(),
+                ))
+                .selected_text(
+                    covered_by
+                        .map(|(c, s)| envelope_name(c, s.as_deref()))
+                        .unwrap_or_default(),
+                )
+                .show_ui(ui, |ui| {
+                    let mut selected = covered_by.cloned();
+                    let mut changed = ui.selectable_value(&mut selected, None, "").changed();
+                    for other in settings.budgets.iter().filter(|b| *b != budget) {
+                        let key = (
+                            other.category().to_string(),
+                            other.sub_category().map(|s| s.to_string()),
+                        );
+                        changed |= ui
+                            .selectable_value(
+                                &mut selected,
+                                Some(key),
+                                envelope_name(other.category(), other.sub_category()),
+                            )
+                            .changed();
+                    }
+                    if changed {
+                        effects.push(Effect::SetBudgetCover(
+                            budget.category().to_string(),
+                            budget.sub_category().map(|s| s.to_string()),
+                            selected,
+                        ));
+                    }
+                });
+            }
+            if ui.button("🗑").clicked() {
+                effects.push(Effect::SetBudget(
+                    budget.category().to_string(),
+                    budget.sub_category().map(|s| s.to_string()),
+                    None,
+                ));
+            }
+            ui.end_row();
+        }
+    });
+
+    let Some(lines) = lines else {
+        return effects;
+    };
+
+    ui.separator();
+
+    let form_id = ui.id().with("budget_form");
+    let (mut category, mut sub_category, mut amount) = ui.data(|d| {
+        d.get_temp::<(String, Option<String>, f32)>(form_id)
+            .unwrap_or_default()
+    });
+
+    ui.horizontal(|ui| {
+        egui::ComboBox::from_id_salt("budget_category")
+            .selected_text(&category)
+            .show_ui(ui, |ui| {
+                for category_ in lines.categories() {
+                    if ui
+                        .selectable_label(&category == category_, category_)
+                        .clicked()
+                    {
+                        category = category_.clone();
+                        sub_category = None;
+                    }
+                }
+            });
+        egui::ComboBox::from_id_salt("budget_sub_category")
+            .selected_text(sub_category.as_deref().unwrap_or("Toutes"))
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut sub_category, None, "Toutes");
+                for (_, sub_category_) in lines
+                    .sub_categories()
+                    .iter()
+                    .filter(|(category_, _)| category_ == &category)
+                {
+                    ui.selectable_value(
+                        &mut sub_category,
+                        Some(sub_category_.clone()),
+                        sub_category_,
+                    );
+                }
+            });
+        ui.add(egui::DragValue::new(&mut amount).speed(10.0));
+        if ui
+            .add_enabled(!category.is_empty(), egui::Button::new("Ajouter"))
+            .clicked()
+        {
+            effects.push(Effect::SetBudget(
+                category.clone(),
+                sub_category.clone(),
+                Some(amount),
+            ));
+        }
+    });
+
+    ui.data_mut(|d| d.insert_temp(form_id, (category, sub_category, amount)));
+
+    if settings.envelope_mode {
+        ui.separator();
+        effects.extend(envelopes(ui, lines, &settings.budgets));
+    }
+
+    effects
+}
+
+/// Envelopes balances by month, after income assignment. Amounts assigned to an
+/// envelope can be changed month by month.
+fn envelopes(ui: &mut egui::Ui, lines: &Lines, budgets: &[Budget]) -> Vec<Effect> {
+    let mut effects = vec![];
+    let income = months_income(lines);
+    let balances = envelope_balances(lines, budgets);
+
+    egui::ScrollArea::horizontal().show(ui, |ui| {
+        egui::Grid::new("envelopes").striped(true).show(ui, |ui| {
+            ui.label(RichText::new("Enveloppe").strong());
+            for month in lines.all_months() {
+                ui.label(RichText::new(month.format("%Y-%m").to_string()).strong());
+            }
+            ui.end_row();
+
+            ui.label("Revenus");
+            for month_income in &income {
+                ui.label(format!("{:>.2}", month_income));
+            }
+            ui.end_row();
+
+            ui.label("À répartir");
+            for (month, month_income) in lines.all_months().iter().zip(&income) {
+                let assigned = budgets.iter().map(|b| b.assigned(*month)).sum::<f32>();
+                let unassigned = month_income - assigned;
+                let text = RichText::new(format!("{:>.2}", unassigned));
+                ui.label(if unassigned < 0.0 {
+                    text.color(ui.visuals().error_fg_color)
+                } else {
+                    text
+                });
+            }
+            ui.end_row();
+
+            for (budget, balances) in budgets.iter().zip(balances) {
+                ui.label(
+                    RichText::new(envelope_name(budget.category(), budget.sub_category())).strong(),
+                );
+                for (month, balance) in lines.all_months().iter().zip(balances) {
+                    ui.vertical(|ui| {
+                        ui.horizontal(|ui| {
+                            let mut assigned = budget.assigned(*month);
+                            if ui
+                                .add(egui::DragValue::new(&mut assigned).speed(10.0).prefix("+ "))
+                                .on_hover_text("Montant attribué ce mois")
+                                .changed()
+                            {
+                                effects.push(Effect::SetBudgetAssigned(
+                                    budget.category().to_string(),
+                                    budget.sub_category().map(|s| s.to_string()),
+                                    *month,
+                                    Some(assigned),
+                                ));
+                            }
+                            if budget.is_assigned(*month)
+                                && ui
+                                    .small_button("↺")
+                                    .on_hover_text("Revenir au budget mensuel")
+                                    .clicked()
+                            {
+                                effects.push(Effect::SetBudgetAssigned(
+                                    budget.category().to_string(),
+                                    budget.sub_category().map(|s| s.to_string()),
+                                    *month,
+                                    None,
+                                ));
+                            }
+                        });
+                        let text = RichText::new(format!("{:>.2}", balance));
+                        ui.label(if balance < 0.0 {
+                            text.color(ui.visuals().error_fg_color)
+                        } else {
+                            text
+                        });
+                    });
+                }
+                ui.end_row();
+            }
+        });
+    });
+
+    effects
+}
+
+fn envelope_name(category: &str, sub_category: Option<&str>) -> String {
+    match sub_category {
+        Some(sub_category) => format!("{category} / {sub_category}"),
+        None => category.to_string(),
+    }
+}
+
+/// Month amount of a category with its budget and what is left, coloured when
+/// overspent. See [`budget_legend`].
+pub fn month_cell(ui: &mut egui::Ui, actual: f32, budget: Option<&Budget>) {
+    let Some(budget) = budget else {
+        ui.label(format!("{:>.2}", actual));
+        return;
+    };
+
+    let remaining = budget.remaining(actual);
+    let status = match (budget.amount() < 0.0, remaining < 0.0) {
+        (true, false) => format!("reste {:>.2}", remaining),
+        (true, true) => format!("dépassé de {:>.2}", -remaining),
+        (false, false) => format!("+{:>.2} reçus", remaining),
+        (false, true) => format!("manque {:>.2}", -remaining),
+    };
+    let color = if budget.is_overspent(actual) {
+        ui.visuals().error_fg_color
+    } else {
+        ui.visuals().text_color()
+    };
+    ui.vertical(|ui| {
+        ui.label(RichText::new(format!("{:>.2}", actual)).color(color));
+        ui.label(
+            RichText::new(format!("/ {:>.2} – {status}", budget.amount()))
+                .small()
+                .color(color),
+        );
+    });
+}
+
+/// Explain budgeted months cells, under tables using [`month_cell`]
+pub fn budget_legend(ui: &mut egui::Ui, settings: &Settings) {
+    if settings.budgets.is_empty() {
+        return;
+    }
+    ui.label(
+        RichText::new(
+            "Mois budgétés : réel / budget – reste à dépenser, ou dépassement en rouge. \
+             Pour les revenus : montant reçu en plus, ou manquant en rouge.",
+        )
+        .small()
+        .weak(),
+    );
+}
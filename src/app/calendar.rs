@@ -0,0 +1,156 @@
+use chrono::Datelike;
+use eframe::egui::{self, Color32, RichText, Sense, Vec2};
+use egui_plot::{Bar, BarChart, Plot};
+
+use crate::{
+    calendar::{month_day_spending, weekday_spending},
+    line::Lines,
+};
+
+use super::effect::Effect;
+
+const CELL_SIZE: f32 = 12.0;
+const CELL_SPACING: f32 = 2.0;
+const WEEKDAYS: [&str; 7] = ["Lun", "Mar", "Mer", "Jeu", "Ven", "Sam", "Dim"];
+
+pub fn render(ui: &mut egui::Ui, lines: &Lines) -> Vec<Effect> {
+    let mut effects = vec![];
+
+    let selected_id = ui.id().with("calendar_selected_day");
+    let mut selected = ui.data(|d| d.get_temp::<chrono::NaiveDate>(selected_id));
+
+    let daily = lines.daily_spending();
+    let max = daily.iter().map(|(_, s)| *s).fold(0.0, f32::max);
+
+    egui::ScrollArea::horizontal().show(ui, |ui| {
+        let Some((first_day, _)) = daily.first() else {
+            return;
+        };
+        let first_monday =
+            *first_day - chrono::Days::new(first_day.weekday().num_days_from_monday() as u64);
+        let weeks = daily
+            .last()
+            .map(|(d, _)| (*d - first_monday).num_days() / 7 + 1)
+            .unwrap_or(1);
+        let step = CELL_SIZE + CELL_SPACING;
+        let (rect, _) =
+            ui.allocate_exact_size(Vec2::new(weeks as f32 * step, 7.0 * step), Sense::hover());
+
+        for (day, spending) in daily {
+            let week = (*day - first_monday).num_days() / 7;
+            let weekday = day.weekday().num_days_from_monday();
+            let cell = egui::Rect::from_min_size(
+                rect.min + Vec2::new(week as f32 * step, weekday as f32 * step),
+                Vec2::splat(CELL_SIZE),
+            );
+            let response = ui
+                .interact(cell, selected_id.with(day), Sense::click())
+                .on_hover_text(format!(
+                    "{} {} : {:>.2}",
+                    WEEKDAYS[weekday as usize],
+                    day.format("%d/%m/%Y"),
+                    spending
+                ));
+            if response.clicked() {
+                selected = Some(*day);
+            }
+
+            let intensity = if max > 0.0 { spending / max } else { 0.0 };
+            let color = if *spending == 0.0 {
+                ui.visuals().faint_bg_color
+            } else {
+                Color32::from_rgb(
+                    (255.0 - intensity * 200.0) as u8,
+                    (230.0 - intensity * 180.0) as u8,
+                    (230.0 - intensity * 180.0) as u8,
+                )
+            };
+            ui.painter().rect_filled(cell, 2.0, color);
+            if selected == Some(*day) {
+                ui.painter().rect_stroke(
+                    cell,
+                    2.0,
+                    ui.visuals().selection.stroke,
+                    egui::StrokeKind::Outside,
+                );
+            }
+        }
+    });
+
+    if let Some(day) = selected {
+        ui.separator();
+        ui.horizontal(|ui| {
+            ui.heading(day.format("%d/%m/%Y").to_string());
+            if ui.button("👓").clicked() {
+                effects.push(Effect::ShowLines(
+                    None,
+                    None,
+                    day.format("%d/%m/%Y").to_string(),
+                ));
+            }
+        });
+        egui::Grid::new("calendar_day_lines")
+            .striped(true)
+            .show(ui, |ui| {
+                for line in lines.lines().iter().filter(|l| l.date() == day) {
+                    ui.label(line.libelle_operation());
+                    ui.label(line.categorie());
+                    ui.label(line.sous_categorie());
+                    ui.label(RichText::new(format!("{:>.2}", line.amount())).strong());
+                    ui.end_row();
+                }
+            });
+    }
+
+    ui.separator();
+
+    ui.columns(2, |columns| {
+        columns[0].label("Dépenses par jour de la semaine");
+        spending_chart(
+            &mut columns[0],
+            "weekday_spending",
+            &weekday_spending(daily),
+            |i| WEEKDAYS.get(i).map(|d| d.to_string()),
+        );
+        columns[1].label("Dépenses par jour du mois");
+        spending_chart(
+            &mut columns[1],
+            "month_day_spending",
+            &month_day_spending(daily),
+            |i| (i < 31).then(|| (i + 1).to_string()),
+        );
+    });
+
+    ui.data_mut(|d| match selected {
+        Some(day) => d.insert_temp(selected_id, day),
+        None => d.remove::<chrono::NaiveDate>(selected_id),
+    });
+
+    effects
+}
+
+fn spending_chart(
+    ui: &mut egui::Ui,
+    id: &str,
+    values: &[f32],
+    label: impl Fn(usize) -> Option<String> + 'static,
+) {
+    Plot::new(id)
+        .height(160.0)
+        .x_axis_formatter(move |mark, _| {
+            if mark.value < 0.0 || mark.value.fract() != 0.0 {
+                return String::new();
+            }
+            label(mark.value as usize).unwrap_or_default()
+        })
+        .show(ui, |plot_ui| {
+            plot_ui.bar_chart(BarChart::new(
+                id,
+                values
+                    .iter()
+                    .enumerate()
+                    .map(|(i, v)| Bar::new(i as f64, *v as f64).width(0.8))
+                    .collect(),
+            ));
+        });
+}
@@ -0,0 +1,265 @@
+use eframe::egui::{self, Align2, Color32, FontId, Pos2, Rect, RichText, Sense, Vec2};
+
+use crate::{
+    breakdown::{CashFlow, cash_flow},
+    line::Lines,
+};
+
+use super::{breakdown::color, effect::Effect};
+
+const HEIGHT: f32 = 480.0;
+const NODE_WIDTH: f32 = 12.0;
+const NODE_SPACING: f32 = 10.0;
+/// Room kept on the right for the last column labels
+const LABELS_WIDTH: f32 = 160.0;
+/// Sampled points of a link curve
+const LINK_STEPS: usize = 24;
+const INCOME_COLOR: Color32 = Color32::from_rgb(60, 150, 60);
+const SAVINGS_COLOR: Color32 = Color32::from_rgb(70, 130, 200);
+const BUDGET_COLOR: Color32 = Color32::GRAY;
+
+/// Diagram node: a rectangle standing for an amount
+struct Node {
+    rect: Rect,
+    label: String,
+    amount: f32,
+    color: Color32,
+    /// Category and sub-category of the node lines
+    lines: Option<(String, Option<String>)>,
+}
+
+pub fn render(ui: &mut egui::Ui, lines: &Lines) -> Vec<Effect> {
+    let mut effects = vec![];
+
+    let cash_flow = cash_flow(lines);
+    let total = cash_flow.total();
+    ui.label(format!(
+        "Revenus : {:>.2} – Dépenses : {:>.2} – Épargne : {:>.2}",
+        cash_flow.incomes().iter().map(|s| s.amount()).sum::<f32>(),
+        cash_flow
+            .expenses()
+            .iter()
+            .map(|(s, _)| s.amount())
+            .sum::<f32>(),
+        cash_flow.savings(),
+    ));
+    ui.separator();
+
+    if total <= 0.0 {
+        ui.label("Aucun mouvement sur la période");
+        return effects;
+    }
+
+    egui::ScrollArea::vertical().show(ui, |ui| {
+        let (rect, _) =
+            ui.allocate_exact_size(Vec2::new(ui.available_width(), HEIGHT), Sense::hover());
+        let layout = layout(&cash_flow, rect);
+
+        for (from, to, height, color) in &layout.links {
+            link(ui, *from, *to, *height, *color);
+        }
+        for (i, node) in layout.nodes.iter().enumerate() {
+            ui.painter().rect_filled(node.rect, 0.0, node.color);
+            if node.rect.height() >= 8.0 {
+                ui.painter().text(
+                    Pos2::new(node.rect.right() + 4.0, node.rect.center().y),
+                    Align2::LEFT_CENTER,
+                    &node.label,
+                    FontId::proportional(12.0),
+                    ui.visuals().text_color(),
+                );
+            }
+            let response = ui
+                .interact(
+                    node.rect,
+                    ui.id().with(("cash_flow_node", i)),
+                    Sense::click(),
+                )
+                .on_hover_text(format!(
+                    "{}\n{:>.2} ({:>.1}%)",
+                    node.label,
+                    node.amount,
+                    node.amount / total * 100.0
+                ));
+            if response.clicked()
+                && let Some((category, sub_category)) = &node.lines
+            {
+                effects.push(Effect::ShowLines(
+                    Some(category.clone()),
+                    sub_category.clone(),
+                    String::new(),
+                ));
+            }
+        }
+    });
+
+    ui.label(
+        RichText::new("Cliquer sur une catégorie pour afficher ses lignes")
+            .small()
+            .weak(),
+    );
+
+    effects
+}
+
+/// Nodes and links (from node, to node, band height, color) of the diagram
+struct Layout {
+    nodes: Vec<Node>,
+    links: Vec<(Pos2, Pos2, f32, Color32)>,
+}
+
+/// Place income sources, budget, expense categories and sub-categories columns
+fn layout(cash_flow: &CashFlow, rect: Rect) -> Layout {
+    let total = cash_flow.total();
+    let column_step = (rect.width() - NODE_WIDTH - LABELS_WIDTH).max(0.0) / 3.0;
+    let column_x = |column: usize| rect.left() + column as f32 * column_step;
+    let sources = cash_flow.incomes().len() + usize::from(cash_flow.savings() < 0.0);
+    let sinks = cash_flow.expenses().len() + usize::from(cash_flow.savings() > 0.0);
+    let spacing = NODE_SPACING * sources.max(sinks).saturating_sub(1) as f32;
+    let scale = (rect.height() - spacing).max(0.0) / total;
+    let node_rect = |column: usize, top: f32, amount: f32| {
+        Rect::from_min_size(
+            Pos2::new(column_x(column), top),
+            Vec2::new(NODE_WIDTH, amount * scale),
+        )
+    };
+
+    let mut nodes = vec![];
+    let mut links = vec![];
+
+    // Budget node, fed by sources and feeding expenses and savings
+    let budget = node_rect(1, rect.top(), total);
+    nodes.push(Node {
+        rect: budget,
+        label: "Budget".to_string(),
+        amount: total,
+        color: BUDGET_COLOR,
+        lines: None,
+    });
+
+    let mut sources = cash_flow
+        .incomes()
+        .iter()
+        .map(|income| {
+            (
+                income.label().to_string(),
+                income.amount(),
+                INCOME_COLOR,
+                Some((income.label().to_string(), None)),
+            )
+        })
+        .collect::<Vec<_>>();
+    if cash_flow.savings() < 0.0 {
+        sources.push((
+            "Épargne utilisée".to_string(),
+            -cash_flow.savings(),
+            SAVINGS_COLOR,
+            None,
+        ));
+    }
+    let (mut top, mut budget_top) = (rect.top(), rect.top());
+    for (label, amount, color, lines) in sources {
+        let node = node_rect(0, top, amount);
+        links.push((
+            node.right_top(),
+            Pos2::new(budget.left(), budget_top),
+            node.height(),
+            color,
+        ));
+        nodes.push(Node {
+            rect: node,
+            label,
+            amount,
+            color,
+            lines,
+        });
+        top += node.height() + NODE_SPACING;
+        budget_top += node.height();
+    }
+
+    let (mut top, mut budget_top) = (rect.top(), rect.top());
+    for (i, (category, sub_categories)) in cash_flow.expenses().iter().enumerate() {
+        let node = node_rect(2, top, category.amount());
+        links.push((
+            Pos2::new(budget.right(), budget_top),
+            node.left_top(),
+            node.height(),
+            color(i),
+        ));
+
+        let mut sub_top = top;
+        for sub_category in sub_categories {
+            // Sub-categories split the category node, as refunds may make their
+            // expenses exceed the category ones
+            let sub_node = Rect::from_min_size(
+                Pos2::new(column_x(3), sub_top),
+                Vec2::new(NODE_WIDTH, node.height() * sub_category.ratio()),
+            );
+            links.push((
+                node.right_top() + Vec2::new(0.0, sub_top - top),
+                sub_node.left_top(),
+                sub_node.height(),
+                color(i),
+            ));
+            nodes.push(Node {
+                rect: sub_node,
+                label: sub_category.label().to_string(),
+                amount: sub_category.amount(),
+                color: color(i),
+                lines: Some((
+                    category.label().to_string(),
+                    Some(sub_category.label().to_string()),
+                )),
+            });
+            sub_top += sub_node.height();
+        }
+
+        nodes.push(Node {
+            rect: node,
+            label: category.label().to_string(),
+            amount: category.amount(),
+            color: color(i),
+            lines: Some((category.label().to_string(), None)),
+        });
+        top += node.height() + NODE_SPACING;
+        budget_top += node.height();
+    }
+    if cash_flow.savings() > 0.0 {
+        let node = node_rect(2, top, cash_flow.savings());
+        links.push((
+            Pos2::new(budget.right(), budget_top),
+            node.left_top(),
+            node.height(),
+            SAVINGS_COLOR,
+        ));
+        nodes.push(Node {
+            rect: node,
+            label: "Épargne".to_string(),
+            amount: cash_flow.savings(),
+            color: SAVINGS_COLOR,
+            lines: None,
+        });
+    }
+
+    Layout { nodes, links }
+}
+
+/// Draw a band of the given height between two points (band tops), as a smooth curve
+fn link(ui: &egui::Ui, from: Pos2, to: Pos2, height: f32, color: Color32) {
+    let color = color.gamma_multiply(0.4);
+    let mut mesh = egui::Mesh::default();
+    for step in 0..=LINK_STEPS {
+        let t = step as f32 / LINK_STEPS as f32;
+        let smooth = t * t * (3.0 - 2.0 * t);
+        let x = from.x + (to.x - from.x) * t;
+        let y = from.y + (to.y - from.y) * smooth;
+        mesh.colored_vertex(Pos2::new(x, y), color);
+        mesh.colored_vertex(Pos2::new(x, y + height), color);
+    }
+    for step in 0..LINK_STEPS as u32 {
+        let index = 2 * step;
+        mesh.add_triangle(index, index + 1, index + 2);
+        mesh.add_triangle(index + 1, index + 3, index + 2);
+    }
+    ui.painter().add(mesh);
+}
@@ -1,24 +1,85 @@
+use std::collections::HashMap;
+
 use eframe::egui::{self, RichText};
-use egui_plot::{Legend, Line, Plot, PlotPoints};
+use egui_plot::{Bar, BarChart, Legend, Line, PlotMemory, PlotPoints};
 
-use crate::{app::scale_buttons, line::Lines};
+use crate::{
+    app::scale_buttons,
+    line::Lines,
+    stats::{AverageMode, MonthStats, Statistic},
+};
 
-use super::effect::Effect;
+use super::{
+    budgets::{budget_legend, month_cell},
+    effect::Effect,
+    month_axis::{first_month, month_label, month_plot},
+    settings::{HistogramMode, Settings},
+};
 
-pub fn render(ui: &mut egui::Ui, lines: &mut Lines) -> Vec<Effect> {
+pub fn render(ui: &mut egui::Ui, lines: &mut Lines, settings: &Settings) -> Vec<Effect> {
     let mut effects = vec![];
     effects.extend(scale_buttons(ui));
 
+    let mut totals_sort = lines.parameters.totals_sort;
+    egui::ComboBox::from_label("Trier par")
+        .selected_text(totals_sort.to_string())
+        .show_ui(ui, |ui| {
+            for statistic in Statistic::ALL {
+                ui.selectable_value(&mut totals_sort, statistic, statistic.to_string());
+            }
+        });
+    if totals_sort != lines.parameters.totals_sort {
+        effects.push(Effect::SetTotalsSort(totals_sort));
+    }
+
+    let mut average_mode = lines.parameters.average_mode;
+    ui.horizontal(|ui| {
+        ui.label("Moyenne sur");
+        ui.selectable_value(&mut average_mode, AverageMode::AllMonths, "Tous les mois");
+        ui.selectable_value(
+            &mut average_mode,
+            AverageMode::CompleteMonths,
+            "Mois complets",
+        );
+        ui.selectable_value(&mut average_mode, AverageMode::ActiveMonths, "Mois actifs");
+        let custom = match average_mode {
+            AverageMode::Custom(count) => count,
+            _ => lines.months_count(),
+        };
+        if ui
+            .selectable_label(
+                matches!(average_mode, AverageMode::Custom(_)),
+                "Nombre fixe",
+            )
+            .clicked()
+        {
+            average_mode = AverageMode::Custom(custom);
+        }
+        if let AverageMode::Custom(count) = &mut average_mode {
+            ui.add(egui::DragValue::new(count).range(1..=120).suffix(" mois"));
+        }
+    });
+    if average_mode != lines.parameters.average_mode {
+        effects.push(Effect::SetAverageMode(average_mode));
+    }
+
     egui::Grid::new("categories_totals")
         .striped(true)
         .show(ui, |ui| {
             ui.label("");
             ui.label(RichText::new("Categorie").strong());
-            for month in &lines.active_months {
+            for month in lines.active_months() {
                 ui.label(RichText::new(month.format("%Y-%m").to_string()).strong());
             }
             ui.label(RichText::new("Total").strong());
-            ui.label(RichText::new("Moyenne").strong());
+            ui.label(
+                RichText::new(format!("Moyenne ({})", lines.parameters.average_mode)).strong(),
+            );
+            ui.label(RichText::new("Médiane").strong());
+            ui.label(RichText::new("Min").strong());
+            ui.label(RichText::new("Max").strong());
+            ui.label(RichText::new("Écart type").strong());
+            ui.label(RichText::new("Mois actifs").strong());
             ui.end_row();
 
             for (category, months, total, average) in lines.categories_totals() {
@@ -27,12 +88,14 @@ pub fn render(ui: &mut egui::Ui, lines: &mut Lines) -> Vec<Effect> {
                 }
                 ui.label(category);
 
+                let budget = settings.budget(category, None);
                 for month_total in months {
-                    ui.label(format!("{:>.2}", month_total));
+                    month_cell(ui, *month_total, budget);
                 }
 
                 ui.label(format!("{:>.2}", total));
                 ui.label(format!("{:>.2}", average));
+                stats_cells(ui, months);
                 ui.end_row();
             }
 
@@ -41,31 +104,115 @@ pub fn render(ui: &mut egui::Ui, lines: &mut Lines) -> Vec<Effect> {
             for (_, total) in lines.months_sums() {
                 ui.label(RichText::new(format!("{:>.2}", total)).strong());
             }
-            ui.label("");
-            ui.label("");
+            for _ in 0..7 {
+                ui.label("");
+            }
             ui.end_row();
         });
+    budget_legend(ui, settings);
 
     ui.separator();
 
     ui.collapsing("Histogramme", |ui| {
         ui.checkbox(
-            &mut lines.categories_histogram_display_expenses_only,
+            &mut lines.parameters.categories_histogram_display_expenses_only,
             "Dépenses uniquement",
         );
 
-        let plot = Plot::new("Historique").legend(Legend::default());
+        let mut histogram_mode = settings.histogram_mode;
+        ui.horizontal(|ui| {
+            for mode in [HistogramMode::Lines, HistogramMode::StackedBars] {
+                ui.selectable_value(&mut histogram_mode, mode, mode.to_string());
+            }
+        });
+        if histogram_mode != settings.histogram_mode {
+            effects.push(Effect::SetHistogramMode(histogram_mode));
+        }
+
+        let histogram = lines
+            .categories_histogram()
+            .iter()
+            .filter(|(_, positive, _)| {
+                !lines.parameters.categories_histogram_display_expenses_only || !positive
+            });
+
+        match settings.histogram_mode {
+            HistogramMode::Lines => {
+                let plot = month_plot("Historique", lines).legend(Legend::default());
+
+                let _ = plot.show(ui, |plot_ui| {
+                    for (category, _, values) in histogram {
+                        plot_ui.line(Line::new(category, PlotPoints::from(values.clone())));
+                    }
+                });
+            }
+            HistogramMode::StackedBars => {
+                let first_month = first_month(lines);
+                let plot_id = ui.make_persistent_id("categories_stacked");
+                // Categories hidden from the legend are not stacked on
+                let hidden = PlotMemory::load(ui.ctx(), plot_id)
+                    .map(|m| m.hidden_items)
+                    .unwrap_or_default();
 
-        let _ = plot.show(ui, |plot_ui| {
-            for (category, positive, values) in lines.categories_histogram() {
-                if (!lines.categories_histogram_display_expenses_only
-                    || (lines.categories_histogram_display_expenses_only && !positive))
-                {
-                    plot_ui.line(Line::new(category, PlotPoints::from(values.clone())));
+                // Positive and negative values are stacked apart, from zero
+                let mut tops: HashMap<i64, (f64, f64)> = HashMap::new();
+                let mut charts: Vec<BarChart> = vec![];
+                for (category, _, values) in histogram {
+                    let id = plot_id.with(category);
+                    let visible = !hidden.contains(&id);
+                    let bars = values
+                        .iter()
+                        .map(|[x, y]| {
+                            let (positive_top, negative_top) =
+                                tops.entry(*x as i64).or_insert((0.0, 0.0));
+                            let top = if *y >= 0.0 {
+                                positive_top
+                            } else {
+                                negative_top
+                            };
+                            let bar = Bar::new(*x, *y).name(category).base_offset(*top);
+                            if visible {
+                                *top += *y;
+                            }
+                            bar
+                        })
+                        .collect();
+                    charts.push(
+                        BarChart::new(category, bars)
+                            .id(id)
+                            .width(0.8)
+                            .element_formatter(Box::new(move |bar, _| {
+                                format!(
+                                    "{}\n{}\n{:>.2}",
+                                    bar.name,
+                                    month_label(first_month, bar.argument),
+                                    bar.value
+                                )
+                            })),
+                    );
                 }
+
+                let _ = month_plot("Historique empilé", lines)
+                    .id(plot_id)
+                    .legend(Legend::default())
+                    .show(ui, |plot_ui| {
+                        for chart in charts {
+                            plot_ui.bar_chart(chart);
+                        }
+                    });
             }
-        });
+        }
     });
 
     effects
 }
+
+/// Median, min, max, standard deviation and active months count cells
+pub fn stats_cells(ui: &mut egui::Ui, months: &[f32]) {
+    let stats = MonthStats::new(months);
+    ui.label(format!("{:>.2}", stats.median()));
+    ui.label(format!("{:>.2}", stats.min()));
+    ui.label(format!("{:>.2}", stats.max()));
+    ui.label(format!("{:>.2}", stats.std_dev()));
+    ui.label(stats.active_months().to_string());
+}
@@ -0,0 +1,51 @@
+use eframe::egui;
+use egui_extras::DatePickerButton;
+
+use crate::line::{DateRange, Lines};
+
+use super::effect::Effect;
+
+pub fn render(ui: &mut egui::Ui, lines: &Lines) -> Vec<Effect> {
+    let mut effects = vec![];
+    let mut range = lines.range();
+
+    ui.horizontal(|ui| {
+        ui.label("Période");
+        egui::ComboBox::from_id_salt("date_range")
+            .selected_text(range.to_string())
+            .show_ui(ui, |ui| {
+                for preset in [
+                    DateRange::All,
+                    DateRange::ThisMonth,
+                    DateRange::LastThreeMonths,
+                    DateRange::ThisYear,
+                    DateRange::LastYear,
+                ] {
+                    ui.selectable_value(&mut range, preset, preset.to_string());
+                }
+                if ui
+                    .selectable_label(matches!(range, DateRange::Custom(_, _)), "Personnalisée")
+                    .clicked()
+                {
+                    range = DateRange::Custom(lines.lower_date(), lines.higher_date());
+                }
+            });
+
+        if let DateRange::Custom(start, end) = &mut range {
+            ui.add(DatePickerButton::new(start).id_salt("date_range_start"));
+            ui.add(DatePickerButton::new(end).id_salt("date_range_end"));
+        }
+
+        ui.label(format!(
+            "{} - {}",
+            lines.lower_date().format("%d/%m/%Y"),
+            lines.higher_date().format("%d/%m/%Y")
+        ));
+    });
+
+    if range != lines.range() {
+        effects.push(Effect::SetDateRange(range));
+    }
+
+    effects
+}
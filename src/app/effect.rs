@@ -1,3 +1,10 @@
+use super::settings::HistogramMode;
+use crate::{
+    line::DateRange,
+    recurring::Decision,
+    stats::{AverageMode, Statistic},
+};
+
 pub enum Effect {
     ClearLines,
     IncreaseScale,
@@ -5,5 +12,53 @@ pub enum Effect {
     SelectCategory(Option<String>),
     SelectSubCategory(Option<String>),
     SetFilterText(String),
-    RecomputeRecurring,
+    SelectPayee(Option<String>),
+    /// Show lines of the given category, sub-category and filter in Lines tab
+    ShowLines(Option<String>, Option<String>, String),
+    /// Show lines of the given normalized payee in Lines tab
+    ShowPayeeLines(String),
+    SetRecurringMonths(usize),
+    SetRecurringApprox(f32),
+    SetRecurringSimilarity(f32),
+    SetPriceChangeThreshold(f32),
+    SetRecurringDecision(String, f32, Option<Decision>),
+    SetForecastMonths(usize),
+    SetBalance(Option<f32>),
+    SetOverdraftThreshold(f32),
+    SetBudget(String, Option<String>, Option<f32>),
+    SetBudgetCover(String, Option<String>, Option<(String, Option<String>)>),
+    /// Amount assigned to an envelope in a month, `None` for its monthly budget
+    SetBudgetAssigned(String, Option<String>, chrono::NaiveDate, Option<f32>),
+    SetEnvelopeMode(bool),
+    SetDateRange(DateRange),
+    SetTotalsSort(Statistic),
+    SetAverageMode(AverageMode),
+    SetHistogramMode(HistogramMode),
+    Undo,
+    Redo,
+}
+
+impl Effect {
+    /// Effects modifying user data, recorded in the undo history.
+    pub fn is_undoable(&self) -> bool {
+        matches!(
+            self,
+            Effect::ClearLines
+                | Effect::SetRecurringMonths(_)
+                | Effect::SetRecurringApprox(_)
+                | Effect::SetRecurringSimilarity(_)
+                | Effect::SetPriceChangeThreshold(_)
+                | Effect::SetRecurringDecision(_, _, _)
+                | Effect::SetForecastMonths(_)
+                | Effect::SetBalance(_)
+                | Effect::SetOverdraftThreshold(_)
+                | Effect::SetBudget(_, _, _)
+                | Effect::SetBudgetCover(_, _, _)
+                | Effect::SetBudgetAssigned(_, _, _, _)
+                | Effect::SetEnvelopeMode(_)
+                | Effect::SetDateRange(_)
+                | Effect::SetTotalsSort(_)
+                | Effect::SetAverageMode(_)
+        )
+    }
 }
@@ -0,0 +1,193 @@
+use std::{
+    mem::Discriminant,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    budget::Budget,
+    line::{DateRange, Line, Lines, Parameters},
+    recurring::RecurringDecision,
+};
+
+use super::{effect::Effect, settings::Settings};
+
+/// Maximum number of undo steps kept in memory.
+const MAX_DEPTH: usize = 100;
+/// Consecutive edits of the same kind within this delay (like a slider drag) are
+/// merged into a single undo step.
+const MERGE_DELAY: Duration = Duration::from_millis(1000);
+
+/// State restored by undo/redo: what undoable effects change. Aggregates are
+/// computed again from it.
+#[derive(Clone, Default)]
+pub struct Snapshot {
+    pub lines: Option<LinesState>,
+    pub recurring_decisions: Vec<RecurringDecision>,
+    pub budgets: Vec<Budget>,
+    pub envelope_mode: bool,
+}
+
+/// Imported lines (shared, not copied) with their date range and parameters
+#[derive(Clone)]
+pub struct LinesState {
+    pub name: String,
+    pub all_lines: Arc<Vec<Line>>,
+    pub range: DateRange,
+    pub parameters: Parameters,
+}
+
+impl Snapshot {
+    pub fn new(lines: &Option<Lines>, settings: &Settings) -> Self {
+        Self {
+            lines: lines.as_ref().map(|lines| LinesState {
+                name: lines.name().to_string(),
+                all_lines: lines.all_lines().clone(),
+                range: lines.range(),
+                parameters: lines.parameters.clone(),
+            }),
+            recurring_decisions: settings.recurring_decisions.clone(),
+            budgets: settings.budgets.clone(),
+            envelope_mode: settings.envelope_mode,
+        }
+    }
+}
+
+struct Entry {
+    snapshot: Snapshot,
+    kind: Option<Discriminant<Effect>>,
+    at: Instant,
+}
+
+#[derive(Default)]
+pub struct History {
+    undo: Vec<Entry>,
+    redo: Vec<Snapshot>,
+}
+
+impl History {
+    /// Record the state as it was before an edit. `kind` identifies the edit so that
+    /// repeated edits of the same kind can be merged, the state being taken only
+    /// when not merged.
+    pub fn record(
+        &mut self,
+        before: impl FnOnce() -> Snapshot,
+        kind: Option<Discriminant<Effect>>,
+    ) {
+        let now = Instant::now();
+        self.redo.clear();
+
+        if let Some(last) = self.undo.last_mut()
+            && kind.is_some()
+            && last.kind == kind
+            && now.duration_since(last.at) < MERGE_DELAY
+        {
+            last.at = now;
+            return;
+        }
+
+        self.undo.push(Entry {
+            snapshot: before(),
+            kind,
+            at: now,
+        });
+        if self.undo.len() > MAX_DEPTH {
+            self.undo.remove(0);
+        }
+    }
+
+    pub fn undo(&mut self, current: Snapshot) -> Option<Snapshot> {
+        let entry = self.undo.pop()?;
+        self.redo.push(current);
+        Some(entry.snapshot)
+    }
+
+    pub fn redo(&mut self, current: Snapshot) -> Option<Snapshot> {
+        let snapshot = self.redo.pop()?;
+        self.undo.push(Entry {
+            snapshot: current,
+            kind: None,
+            at: Instant::now(),
+        });
+        Some(snapshot)
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extract::TryIntoLines;
+
+    fn snapshot(name: &str) -> Snapshot {
+        let raw = format!(
+            "header\n22/03/2025;{name};{name};;;Carte bancaire;Alimentation;Alimentation - autre;-3,45;;20/03/2025;24/03/2025;0"
+        );
+        Snapshot::new(
+            &Some(raw.into_lines(name.to_string()).unwrap()),
+            &Default::default(),
+        )
+    }
+
+    fn name(snapshot: &Snapshot) -> String {
+        snapshot.lines.as_ref().unwrap().name.clone()
+    }
+
+    #[test]
+    fn undo_redo() {
+        // Given
+        let mut history = History::default();
+        history.record(|| snapshot("a"), None);
+        history.record(|| snapshot("b"), None);
+
+        // When/Then
+        let restored = history.undo(snapshot("c")).unwrap();
+        assert_eq!(name(&restored), "b");
+        let restored = history.undo(restored).unwrap();
+        assert_eq!(name(&restored), "a");
+        assert!(!history.can_undo());
+
+        let restored = history.redo(restored).unwrap();
+        assert_eq!(name(&restored), "b");
+        let restored = history.redo(restored).unwrap();
+        assert_eq!(name(&restored), "c");
+        assert!(!history.can_redo());
+    }
+
+    #[test]
+    fn record_clears_redo() {
+        // Given
+        let mut history = History::default();
+        history.record(|| snapshot("a"), None);
+        let _ = history.undo(snapshot("b"));
+
+        // When
+        history.record(|| snapshot("a"), None);
+
+        // Then
+        assert!(!history.can_redo());
+    }
+
+    #[test]
+    fn merge_same_kind() {
+        // Given
+        let mut history = History::default();
+        let kind = Some(std::mem::discriminant(&Effect::SetRecurringMonths(1)));
+
+        // When
+        history.record(|| snapshot("a"), kind);
+        history.record(|| panic!("merged edits are not recorded"), kind);
+
+        // Then
+        let restored = history.undo(snapshot("c")).unwrap();
+        assert_eq!(name(&restored), "a");
+        assert!(!history.can_undo());
+    }
+}
@@ -1,17 +1,30 @@
-use eframe::egui::{self, Align, Layout};
+use eframe::egui::{self, Align, Layout, RichText};
 use egui_file_dialog::FileDialog;
-use egui_plot::{Legend, Line, Plot, PlotPoints};
+use egui_plot::{Bar, BarChart, Legend, Line, PlotPoints, Polygon};
 
-use crate::line::Lines;
+use crate::{
+    line::Lines,
+    savings::{MonthFlows, rolling_savings_rate},
+};
 
-use super::effect::Effect;
+use super::{
+    effect::Effect,
+    history::History,
+    history_buttons,
+    month_axis::{first_month, month_label, month_plot},
+};
+
+/// Months count of displayed rolling savings rates
+const ROLLING_WINDOWS: [usize; 3] = [3, 6, 12];
 
 pub fn render(
     ui: &mut egui::Ui,
     file_dialog: &mut FileDialog,
     lines: &mut Option<Lines>,
+    history: &History,
 ) -> Vec<Effect> {
     let mut effects = vec![];
+    effects.extend(history_buttons(ui, history));
 
     ui.with_layout(
         Layout::centered_and_justified(egui::Direction::TopDown),
@@ -20,30 +33,160 @@ pub fn render(
                 Some(lines) => {
                     //
                     ui.with_layout(Layout::top_down_justified(Align::Center), |ui| {
-                        ui.label(format!("Data loaded : {}", lines.name()));
+                        ui.label(format!(
+                            "Data loaded : {} ({} - {})",
+                            lines.name(),
+                            lines.lower_date().format("%d/%m/%Y"),
+                            lines.higher_date().format("%d/%m/%Y"),
+                        ));
                         if ui.button("Clear").clicked() {
                             effects.push(Effect::ClearLines);
                         };
 
                         ui.separator();
 
+                        if let Some(risk) = lines.overdraft_risks().next() {
+                            ui.label(
+                                RichText::new(format!(
+                                    "⚠ Risque de découvert le {} ({} : {:>.2}, solde {:>.2})",
+                                    risk.date().format("%d/%m/%Y"),
+                                    risk.payee(),
+                                    risk.amount(),
+                                    risk.balance(),
+                                ))
+                                .strong()
+                                .color(ui.visuals().error_fg_color),
+                            );
+                            ui.separator();
+                        }
+
+                        if !lines.missed_payments().is_empty() {
+                            ui.label(
+                                RichText::new("⚠ Paiements récurrents manquants")
+                                    .strong()
+                                    .color(ui.visuals().warn_fg_color),
+                            );
+                            egui::Grid::new("missed_payments")
+                                .striped(true)
+                                .show(ui, |ui| {
+                                    for missed_payment in lines.missed_payments() {
+                                        ui.label(
+                                            RichText::new(missed_payment.payee())
+                                                .color(ui.visuals().warn_fg_color),
+                                        );
+                                        ui.label(missed_payment.periodicity().to_string());
+                                        ui.label(format!(
+                                            "Attendu le {}",
+                                            missed_payment.expected_date().format("%d/%m/%Y")
+                                        ));
+                                        ui.label(format!(
+                                            "{:>.2}",
+                                            missed_payment.expected_amount()
+                                        ));
+                                        ui.end_row();
+                                    }
+                                });
+
+                            ui.separator();
+                        }
+
+                        let rolling = ROLLING_WINDOWS
+                            .map(|window| rolling_savings_rate(lines.months_flows(), window));
                         egui::Grid::new("lines").striped(true).show(ui, |ui| {
-                            for (date, sum) in lines.months_sums() {
-                                ui.label(date.format("%Y-%m").to_string());
-                                ui.label(format!("{sum}"));
+                            ui.label(RichText::new("Mois").strong());
+                            ui.label(RichText::new("Revenus").strong());
+                            ui.label(RichText::new("Dépenses").strong());
+                            ui.label(RichText::new("Net").strong());
+                            ui.label(RichText::new("Épargne").strong());
+                            for window in ROLLING_WINDOWS {
+                                ui.label(RichText::new(format!("Épargne {window} mois")).strong());
+                            }
+                            ui.end_row();
+
+                            for (i, flows) in lines.months_flows().iter().enumerate() {
+                                ui.label(flows.month().format("%Y-%m").to_string());
+                                ui.label(format!("{:>.2}", flows.income()));
+                                ui.label(format!("{:>.2}", flows.expenses()));
+                                ui.label(format!("{:>.2}", flows.net()));
+                                ui.label(rate(flows.savings_rate()));
+                                for rates in &rolling {
+                                    ui.label(rate(rates[i]));
+                                }
                                 ui.end_row();
                             }
                         });
 
                         ui.separator();
 
+                        ui.collapsing("Revenus et dépenses", |ui| {
+                            let first_month = first_month(lines);
+                            month_plot("months_flows", lines)
+                                .legend(Legend::default())
+                                .height(240.0)
+                                .show(ui, |plot_ui| {
+                                    let flows = lines.months_flows();
+                                    let bars = |value: fn(&MonthFlows) -> f32| {
+                                        flows
+                                            .iter()
+                                            .enumerate()
+                                            .map(|(i, f)| {
+                                                Bar::new(i as f64, value(f) as f64).width(0.6)
+                                            })
+                                            .collect::<Vec<Bar>>()
+                                    };
+                                    let formatter = |name: &'static str| {
+                                        Box::new(move |bar: &Bar, _: &BarChart| {
+                                            format!(
+                                                "{}\n{}\n{:>.2}",
+                                                name,
+                                                month_label(first_month, bar.argument),
+                                                bar.value
+                                            )
+                                        })
+                                    };
+                                    plot_ui.bar_chart(
+                                        BarChart::new("Revenus", bars(MonthFlows::income))
+                                            .color(egui::Color32::DARK_GREEN)
+                                            .element_formatter(formatter("Revenus")),
+                                    );
+                                    plot_ui.bar_chart(
+                                        BarChart::new("Dépenses", bars(MonthFlows::expenses))
+                                            .color(egui::Color32::DARK_RED)
+                                            .element_formatter(formatter("Dépenses")),
+                                    );
+                                    plot_ui.line(Line::new(
+                                        "Net",
+                                        PlotPoints::from(
+                                            flows
+                                                .iter()
+                                                .enumerate()
+                                                .map(|(i, f)| [i as f64, f.net() as f64])
+                                                .collect::<Vec<[f64; 2]>>(),
+                                        ),
+                                    ));
+                                });
+                        });
+
+                        ui.separator();
+
                         ui.collapsing("Histogramme", |ui| {
                             ui.checkbox(
-                                &mut lines.categories_histogram_display_expenses_only,
+                                &mut lines.parameters.categories_histogram_display_expenses_only,
                                 "Dépenses uniquement",
                             );
 
-                            let plot = Plot::new("Historique").legend(Legend::default());
+                            let mut forecast_months = lines.parameters.forecast_months;
+                            if ui
+                                .add(
+                                    egui::Slider::new(&mut forecast_months, 0..=24)
+                                        .text("Mois de prévision"),
+                                )
+                                .changed()
+                            {
+                                effects.push(Effect::SetForecastMonths(forecast_months));
+                            };
+
+                            let plot = month_plot("Historique", lines).legend(Legend::default());
 
                             let _ = plot.show(ui, |plot_ui| {
                                 plot_ui.line(Line::new(
@@ -57,6 +200,74 @@ pub fn render(
                                             .collect::<Vec<[f64; 2]>>(),
                                     ),
                                 ));
+
+                                if let Some((_, last_sum)) = lines.months_sums().last()
+                                    && !lines.forecast().is_empty()
+                                {
+                                    let last = lines.months_sums().len() - 1;
+                                    let forecast = lines
+                                        .forecast()
+                                        .iter()
+                                        .enumerate()
+                                        .map(|(i, f)| ((last + i + 1) as f64, f))
+                                        .collect::<Vec<_>>();
+                                    let start = [last as f64, *last_sum as f64];
+
+                                    let upper = forecast
+                                        .iter()
+                                        .map(|(x, f)| [*x, (f.balance() + f.uncertainty()) as f64]);
+                                    let lower = forecast
+                                        .iter()
+                                        .rev()
+                                        .map(|(x, f)| [*x, (f.balance() - f.uncertainty()) as f64]);
+                                    plot_ui.polygon(
+                                        Polygon::new(
+                                            "Incertitude",
+                                            PlotPoints::from(
+                                                std::iter::once(start)
+                                                    .chain(upper)
+                                                    .chain(lower)
+                                                    .collect::<Vec<[f64; 2]>>(),
+                                            ),
+                                        )
+                                        .stroke(egui::Stroke::NONE),
+                                    );
+                                    plot_ui.line(
+                                        Line::new(
+                                            "Prévision",
+                                            PlotPoints::from(
+                                                std::iter::once(start)
+                                                    .chain(
+                                                        forecast
+                                                            .iter()
+                                                            .map(|(x, f)| [*x, f.balance() as f64]),
+                                                    )
+                                                    .collect::<Vec<[f64; 2]>>(),
+                                            ),
+                                        )
+                                        .style(egui_plot::LineStyle::dashed_dense()),
+                                    );
+                                }
+                            });
+
+                            egui::Grid::new("forecast").striped(true).show(ui, |ui| {
+                                ui.label(RichText::new("Mois").strong());
+                                ui.label(RichText::new("Revenus").strong());
+                                ui.label(RichText::new("Dépenses").strong());
+                                ui.label(RichText::new("Solde").strong());
+                                ui.end_row();
+
+                                for forecast in lines.forecast() {
+                                    ui.label(forecast.month().format("%Y-%m").to_string());
+                                    ui.label(format!("{:>.2}", forecast.income()));
+                                    ui.label(format!("{:>.2}", forecast.expenses()));
+                                    ui.label(format!(
+                                        "{:>.2} ± {:>.2}",
+                                        forecast.balance(),
+                                        forecast.uncertainty()
+                                    ));
+                                    ui.end_row();
+                                }
                             });
                         });
                     });
@@ -75,3 +286,7 @@ pub fn render(
 
     effects
 }
+
+fn rate(rate: Option<f32>) -> String {
+    rate.map(|r| format!("{:>.1}%", r)).unwrap_or_default()
+}
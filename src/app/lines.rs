@@ -10,6 +10,7 @@ pub fn render<'a>(
     lines: &mut Lines,
     selected_category: &'a Option<String>,
     selected_sub_category: &'a Option<String>,
+    selected_payee: &'a Option<String>,
     filter_text: &'a str,
 ) -> Vec<Effect> {
     let mut effects = vec![];
@@ -60,6 +61,14 @@ pub fn render<'a>(
 
         ui.separator();
 
+        if let Some(selected_payee) = selected_payee {
+            ui.label(format!("Bénéficiaire : {selected_payee}"));
+            if ui.small_button("✖").clicked() {
+                effects.push(Effect::SelectPayee(None));
+            }
+            ui.separator();
+        }
+
         let mut filter_text_ = filter_text.to_string();
         if ui
             .add(egui::TextEdit::singleline(&mut filter_text_))
@@ -80,19 +89,26 @@ pub fn render<'a>(
 
     egui::Grid::new("lines").striped(true).show(ui, |ui| {
         for line in lines.lines() {
-            if let Some(selected_category) = selected_category {
-                if line.categorie() != selected_category {
-                    continue;
-                }
+            if let Some(selected_category) = selected_category
+                && line.categorie() != selected_category
+            {
+                continue;
             }
 
-            if let Some(selected_sub_category) = selected_sub_category {
-                if line.sous_categorie() != selected_sub_category {
-                    continue;
-                }
+            if let Some(selected_sub_category) = selected_sub_category
+                && line.sous_categorie() != selected_sub_category
+            {
+                continue;
+            }
+
+            if let Some(selected_payee) = selected_payee
+                && line.payee() != selected_payee
+            {
+                continue;
             }
 
             if !filter_text.is_empty()
+                && !line.date_raw().contains(filter_text)
                 && !line
                     .libelle_simplifie()
                     .to_lowercase()
@@ -131,20 +147,20 @@ pub fn render<'a>(
                 chrono::NaiveDate::from_ymd_opt(year, month, 1).unwrap()
             };
 
-            if let Some(current_date) = current_date {
-                if date.year() != current_date.year() || date.month() != current_date.month() {
-                    ui.label("");
-                    ui.label("");
-                    ui.label("");
-                    ui.label("");
-                    ui.label("");
-                    ui.label(RichText::new(format!("{:>.2}", current_debit)).strong());
-                    ui.label(RichText::new(format!("{:>.2}", current_credit)).strong());
-                    ui.end_row();
-                    ui.end_row();
-                    current_debit = 0.0;
-                    current_credit = 0.0;
-                }
+            if let Some(current_date) = current_date
+                && (date.year() != current_date.year() || date.month() != current_date.month())
+            {
+                ui.label("");
+                ui.label("");
+                ui.label("");
+                ui.label("");
+                ui.label("");
+                ui.label(RichText::new(format!("{:>.2}", current_debit)).strong());
+                ui.label(RichText::new(format!("{:>.2}", current_credit)).strong());
+                ui.end_row();
+                ui.end_row();
+                current_debit = 0.0;
+                current_credit = 0.0;
             }
 
             ui.label(line.date_raw());
@@ -0,0 +1,135 @@
+use std::cmp::Ordering;
+
+use derive_more::Display;
+use eframe::egui::{self, RichText};
+use egui_plot::{Bar, BarChart, Plot};
+
+use crate::{line::Lines, merchant::Merchant};
+
+use super::{effect::Effect, sparkline::sparkline};
+
+#[derive(Debug, Clone, Copy, PartialEq, Display)]
+enum SortBy {
+    #[display("Total")]
+    Total,
+    #[display("Nombre")]
+    Count,
+    #[display("Panier moyen")]
+    AverageTicket,
+    #[display("Dernière date")]
+    LastDate,
+}
+
+impl SortBy {
+    fn compare(&self, a: &Merchant, b: &Merchant) -> Ordering {
+        match self {
+            SortBy::Total => a.total().total_cmp(&b.total()),
+            SortBy::Count => b.count().cmp(&a.count()),
+            SortBy::AverageTicket => a.average_ticket().total_cmp(&b.average_ticket()),
+            SortBy::LastDate => b.last_date().cmp(&a.last_date()),
+        }
+    }
+}
+
+pub fn render(ui: &mut egui::Ui, lines: &Lines) -> Vec<Effect> {
+    let mut effects = vec![];
+
+    let state_id = ui.id().with("merchants");
+    let (mut category, mut sort_by, mut top) = ui.data(|d| {
+        d.get_temp::<(Option<String>, SortBy, usize)>(state_id)
+            .unwrap_or((None, SortBy::Total, 10))
+    });
+
+    ui.horizontal(|ui| {
+        egui::ComboBox::from_label("Categorie")
+            .selected_text(category.clone().unwrap_or_default())
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut category, None, "");
+                for category_ in lines.categories() {
+                    ui.selectable_value(&mut category, Some(category_.clone()), category_);
+                }
+            });
+        egui::ComboBox::from_label("Tri")
+            .selected_text(sort_by.to_string())
+            .show_ui(ui, |ui| {
+                for sort_by_ in [
+                    SortBy::Total,
+                    SortBy::Count,
+                    SortBy::AverageTicket,
+                    SortBy::LastDate,
+                ] {
+                    ui.selectable_value(&mut sort_by, sort_by_, sort_by_.to_string());
+                }
+            });
+        ui.add(egui::Slider::new(&mut top, 1..=30).text("Top"));
+    });
+
+    let mut merchants = lines
+        .merchants()
+        .iter()
+        .filter(|m| category.as_ref().is_none_or(|c| m.category() == c))
+        .collect::<Vec<&Merchant>>();
+    merchants.sort_by(|a, b| sort_by.compare(a, b));
+
+    ui.separator();
+
+    Plot::new("merchants_top")
+        .height(200.0)
+        .show_axes([false, true])
+        .show(ui, |plot_ui| {
+            plot_ui.bar_chart(BarChart::new(
+                "Top",
+                merchants
+                    .iter()
+                    .take(top)
+                    .enumerate()
+                    .map(|(i, m)| Bar::new(i as f64, m.total() as f64).name(m.payee()))
+                    .collect(),
+            ));
+        });
+
+    ui.separator();
+
+    egui::ScrollArea::vertical().show(ui, |ui| {
+        egui::Grid::new("merchants").striped(true).show(ui, |ui| {
+            ui.label("");
+            ui.label(RichText::new("Bénéficiaire").strong());
+            ui.label(RichText::new("Categorie").strong());
+            ui.label(RichText::new("Total").strong());
+            ui.label(RichText::new("Nombre").strong());
+            ui.label(RichText::new("Panier moyen").strong());
+            ui.label(RichText::new("Première").strong());
+            ui.label(RichText::new("Dernière").strong());
+            ui.label(RichText::new("Par mois").strong());
+            ui.end_row();
+
+            for merchant in merchants {
+                if ui.button("👓").clicked() {
+                    effects.push(Effect::ShowPayeeLines(merchant.payee().to_string()));
+                }
+                ui.label(RichText::new(merchant.payee()).strong());
+                ui.label(merchant.category());
+                ui.label(format!("{:>.2}", merchant.total()));
+                ui.label(merchant.count().to_string());
+                ui.label(format!("{:>.2}", merchant.average_ticket()));
+                ui.label(merchant.first_date().format("%d/%m/%Y").to_string());
+                ui.label(merchant.last_date().format("%d/%m/%Y").to_string());
+                sparkline(
+                    ui,
+                    ("merchant_sparkline", merchant.payee()),
+                    merchant
+                        .months()
+                        .iter()
+                        .enumerate()
+                        .map(|(i, v)| [i as f64, *v as f64])
+                        .collect(),
+                );
+                ui.end_row();
+            }
+        });
+    });
+
+    ui.data_mut(|d| d.insert_temp(state_id, (category, sort_by, top)));
+
+    effects
+}
@@ -1,22 +1,45 @@
 use std::{fs, path::PathBuf};
 
-use derive_more::{Constructor, Display};
+use derive_more::Display;
 use effect::Effect;
-use eframe::egui::{self, Align, Layout};
+use eframe::egui::{self, Key, KeyboardShortcut, Modifiers};
 use egui_dock::{DockArea, DockState, Style};
 use egui_file_dialog::FileDialog;
 
+pub mod anomalies;
+pub mod balance;
+pub mod breakdown;
+pub mod budgets;
+pub mod calendar;
+pub mod cash_flow;
 pub mod categories;
+pub mod date_range;
 pub mod effect;
+pub mod history;
 pub mod home;
 pub mod lines;
+pub mod merchants;
+pub mod month_axis;
+pub mod period_comparison;
 pub mod recurring;
+pub mod settings;
+pub mod sparkline;
 pub mod sub_categories;
+pub mod year_over_year;
 
 use crate::{
-    extract::{TryIntoLines, extract_recuring},
+    extract::{
+        TryIntoLines, build_lines_with, extract_balance_timeline, update_forecast,
+        update_recurring, with_range,
+    },
     line::Lines,
 };
+use history::{History, Snapshot};
+use settings::Settings;
+
+const UNDO_SHORTCUT: KeyboardShortcut = KeyboardShortcut::new(Modifiers::COMMAND, Key::Z);
+const REDO_SHORTCUT: KeyboardShortcut =
+    KeyboardShortcut::new(Modifiers::COMMAND.plus(Modifiers::SHIFT), Key::Z);
 
 pub struct MyApp {
     start_from: Option<PathBuf>,
@@ -26,7 +49,11 @@ pub struct MyApp {
     tree: DockState<Tab>,
     selected_category: Option<String>,
     selected_sub_category: Option<String>,
+    /// Normalized payee of the lines shown in Lines tab
+    selected_payee: Option<String>,
     filter_text: String,
+    history: History,
+    settings: Settings,
 }
 
 fn dock() -> DockState<Tab> {
@@ -36,11 +63,25 @@ fn dock() -> DockState<Tab> {
         Tab::SubCategories,
         Tab::Lines,
         Tab::Recurring,
+        Tab::Balance,
+        Tab::Budgets,
+        Tab::YearOverYear,
+        Tab::PeriodComparison,
+        Tab::Merchants,
+        Tab::Calendar,
+        Tab::Anomalies,
+        Tab::Breakdown,
+        Tab::CashFlow,
     ])
 }
 
 impl MyApp {
-    pub fn new(start_from: Option<PathBuf>) -> Self {
+    pub fn new(cc: &eframe::CreationContext<'_>, start_from: Option<PathBuf>) -> Self {
+        let settings = cc
+            .storage
+            .and_then(|storage| eframe::get_value(storage, eframe::APP_KEY))
+            .unwrap_or_default();
+
         Self {
             start_from,
             file_dialog: Default::default(),
@@ -49,16 +90,49 @@ impl MyApp {
             tree: dock(),
             selected_category: Default::default(),
             selected_sub_category: Default::default(),
+            selected_payee: Default::default(),
             filter_text: "".to_string(),
+            history: Default::default(),
+            settings,
+        }
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        Snapshot::new(&self.lines, &self.settings)
+    }
+
+    /// Restore undoable state, other settings (like display modes) being kept
+    fn restore(&mut self, snapshot: Snapshot) {
+        self.settings.recurring_decisions = snapshot.recurring_decisions;
+        self.settings.budgets = snapshot.budgets;
+        self.settings.envelope_mode = snapshot.envelope_mode;
+        self.lines = snapshot.lines.map(|lines| {
+            build_lines_with(
+                lines.name,
+                lines.all_lines,
+                lines.range,
+                lines.parameters,
+                &self.settings.recurring_decisions,
+            )
+        });
+    }
+
+    fn update_recurring(&mut self) {
+        if let Some(lines) = &mut self.lines {
+            update_recurring(lines, &self.settings.recurring_decisions);
         }
     }
 }
 
 impl eframe::App for MyApp {
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        eframe::set_value(storage, eframe::APP_KEY, &self.settings);
+    }
+
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
         ctx.set_zoom_factor(self.scale_factor);
 
-        egui::CentralPanel::default().show(ctx, |_ui| {
+        egui::CentralPanel::default().show(ctx, |ui| {
             self.file_dialog.update(ctx);
 
             if let Some(path) = self.file_dialog.take_picked() {
@@ -68,7 +142,10 @@ impl eframe::App for MyApp {
                     .file_stem()
                     .map(|n| n.display().to_string())
                     .unwrap_or("data.csv".to_string());
+                self.history
+                    .record(|| Snapshot::new(&self.lines, &self.settings), None);
                 self.lines = Some(raw.into_lines(name).unwrap());
+                self.update_recurring();
                 self.tree = dock();
             }
 
@@ -80,28 +157,53 @@ impl eframe::App for MyApp {
                     .map(|n| n.display().to_string())
                     .unwrap_or("data.csv".to_string());
                 self.lines = Some(raw.into_lines(name).unwrap());
+                self.update_recurring();
                 self.tree = dock();
                 self.start_from = None;
             }
 
             let mut effects: Vec<Effect> = vec![];
 
+            if !ctx.wants_keyboard_input() {
+                // Redo shortcut must be tested first as undo one matches it too
+                if ctx.input_mut(|i| i.consume_shortcut(&REDO_SHORTCUT)) {
+                    effects.push(Effect::Redo);
+                } else if ctx.input_mut(|i| i.consume_shortcut(&UNDO_SHORTCUT)) {
+                    effects.push(Effect::Undo);
+                }
+            }
+
+            if let Some(lines) = &self.lines {
+                effects.extend(date_range::render(ui, lines));
+                ui.separator();
+            }
+
             DockArea::new(&mut self.tree)
                 .show_close_buttons(false)
                 .style(Style::from_egui(ctx.style().as_ref()))
-                .show(
-                    ctx,
-                    &mut TabViewer::new(
-                        &mut self.lines,
-                        &mut self.file_dialog,
-                        &mut effects,
-                        &self.selected_category,
-                        &self.selected_sub_category,
-                        &self.filter_text,
-                    ),
+                .show_inside(
+                    ui,
+                    &mut TabViewer {
+                        lines: &mut self.lines,
+                        file_dialog: &mut self.file_dialog,
+                        messages: &mut effects,
+                        selected_category: &self.selected_category,
+                        selected_sub_category: &self.selected_sub_category,
+                        selected_payee: &self.selected_payee,
+                        filter_text: &self.filter_text,
+                        history: &self.history,
+                        settings: &self.settings,
+                    },
                 );
 
             while let Some(effect) = effects.pop() {
+                if effect.is_undoable() {
+                    self.history.record(
+                        || Snapshot::new(&self.lines, &self.settings),
+                        Some(std::mem::discriminant(&effect)),
+                    );
+                }
+
                 match effect {
                     Effect::IncreaseScale => {
                         self.scale_factor *= 1.1;
@@ -120,12 +222,125 @@ impl eframe::App for MyApp {
                     Effect::SetFilterText(value) => {
                         self.filter_text = value;
                     }
+                    Effect::SelectPayee(payee) => {
+                        self.selected_payee = payee;
+                    }
+                    Effect::ShowLines(category, sub_category, filter_text) => {
+                        self.selected_category = category;
+                        self.selected_sub_category = sub_category;
+                        self.selected_payee = None;
+                        self.filter_text = filter_text;
+                        if let Some(tab) = self.tree.find_tab(&Tab::Lines) {
+                            self.tree.set_active_tab(tab);
+                        }
+                    }
+                    Effect::ShowPayeeLines(payee) => {
+                        self.selected_category = None;
+                        self.selected_sub_category = None;
+                        self.selected_payee = Some(payee);
+                        self.filter_text = String::new();
+                        if let Some(tab) = self.tree.find_tab(&Tab::Lines) {
+                            self.tree.set_active_tab(tab);
+                        }
+                    }
                     Effect::ClearLines => {
                         self.lines = None;
                     }
-                    Effect::RecomputeRecurring => {
+                    Effect::SetRecurringMonths(value) => {
+                        if let Some(lines) = &mut self.lines {
+                            lines.parameters.recurring_months = value;
+                        }
+                        self.update_recurring();
+                    }
+                    Effect::SetRecurringApprox(value) => {
+                        if let Some(lines) = &mut self.lines {
+                            lines.parameters.recurring_approx = value;
+                        }
+                        self.update_recurring();
+                    }
+                    Effect::SetRecurringSimilarity(value) => {
+                        if let Some(lines) = &mut self.lines {
+                            lines.parameters.recurring_similarity = value;
+                        }
+                        self.update_recurring();
+                    }
+                    Effect::SetPriceChangeThreshold(value) => {
+                        if let Some(lines) = &mut self.lines {
+                            lines.parameters.price_change_threshold = value;
+                        }
+                        self.update_recurring();
+                    }
+                    Effect::SetRecurringDecision(payee, amount, decision) => {
+                        self.settings
+                            .set_recurring_decision(payee, amount, decision);
+                        self.update_recurring();
+                    }
+                    Effect::SetForecastMonths(value) => {
+                        if let Some(lines) = &mut self.lines {
+                            lines.parameters.forecast_months = value;
+                            update_forecast(lines);
+                        }
+                    }
+                    Effect::SetBalance(value) => {
+                        if let Some(lines) = &mut self.lines {
+                            lines.parameters.balance = value;
+                            lines.recurring.balance_timeline = extract_balance_timeline(lines);
+                        }
+                    }
+                    Effect::SetOverdraftThreshold(value) => {
+                        if let Some(lines) = &mut self.lines {
+                            lines.parameters.overdraft_threshold = value;
+                        }
+                    }
+                    Effect::SetBudget(category, sub_category, amount) => {
+                        self.settings.set_budget(category, sub_category, amount);
+                    }
+                    Effect::SetBudgetCover(category, sub_category, covered_by) => {
+                        self.settings.set_budget_cover(
+                            &category,
+                            sub_category.as_deref(),
+                            covered_by,
+                        );
+                    }
+                    Effect::SetBudgetAssigned(category, sub_category, month, amount) => {
+                        self.settings.set_budget_assigned(
+                            &category,
+                            sub_category.as_deref(),
+                            month,
+                            amount,
+                        );
+                    }
+                    Effect::SetEnvelopeMode(value) => {
+                        self.settings.envelope_mode = value;
+                    }
+                    Effect::SetDateRange(range) => {
+                        if let Some(lines) = &self.lines {
+                            self.lines =
+                                Some(with_range(lines, range, &self.settings.recurring_decisions));
+                        }
+                    }
+                    Effect::SetTotalsSort(statistic) => {
+                        if let Some(lines) = &mut self.lines {
+                            lines.sort_totals(statistic);
+                        }
+                    }
+                    Effect::SetAverageMode(mode) => {
                         if let Some(lines) = &mut self.lines {
-                            lines.recurring = extract_recuring(lines);
+                            lines.set_average_mode(mode);
+                            update_forecast(lines);
+                        }
+                    }
+                    Effect::SetHistogramMode(mode) => {
+                        self.settings.histogram_mode = mode;
+                    }
+                    Effect::Undo => {
+                        if let Some(snapshot) = self.history.undo(self.snapshot()) {
+                            self.restore(snapshot);
+                        }
+                    }
+                    Effect::Redo => {
+                        if let Some(snapshot) = self.history.redo(self.snapshot()) {
+                            self.restore(snapshot);
                         }
                     }
                 }
@@ -134,23 +349,34 @@ impl eframe::App for MyApp {
     }
 }
 
-#[derive(Debug, Display)]
+#[derive(Debug, Display, PartialEq)]
 enum Tab {
     Home,
     Categories,
     SubCategories,
     Lines,
     Recurring,
+    Balance,
+    Budgets,
+    YearOverYear,
+    PeriodComparison,
+    Merchants,
+    Calendar,
+    Anomalies,
+    Breakdown,
+    CashFlow,
 }
 
-#[derive(Constructor)]
 struct TabViewer<'a> {
     lines: &'a mut Option<Lines>,
     file_dialog: &'a mut FileDialog,
     messages: &'a mut Vec<Effect>,
     selected_category: &'a Option<String>,
     selected_sub_category: &'a Option<String>,
+    selected_payee: &'a Option<String>,
     filter_text: &'a String,
+    history: &'a History,
+    settings: &'a Settings,
 }
 
 impl egui_dock::TabViewer for TabViewer<'_> {
@@ -162,17 +388,17 @@ impl egui_dock::TabViewer for TabViewer<'_> {
 
     fn ui(&mut self, ui: &mut egui::Ui, tab: &mut Self::Tab) {
         self.messages.extend(match tab {
-            Tab::Home => home::render(ui, self.file_dialog, self.lines),
+            Tab::Home => home::render(ui, self.file_dialog, self.lines, self.history),
             Tab::Categories => {
                 if let Some(lines) = self.lines {
-                    categories::render(ui, lines)
+                    categories::render(ui, lines, self.settings)
                 } else {
                     vec![]
                 }
             }
             Tab::SubCategories => {
                 if let Some(lines) = self.lines {
-                    sub_categories::render(ui, lines)
+                    sub_categories::render(ui, lines, self.settings)
                 } else {
                     vec![]
                 }
@@ -184,6 +410,7 @@ impl egui_dock::TabViewer for TabViewer<'_> {
                         lines,
                         self.selected_category,
                         self.selected_sub_category,
+                        self.selected_payee,
                         self.filter_text,
                     )
                 } else {
@@ -192,7 +419,64 @@ impl egui_dock::TabViewer for TabViewer<'_> {
             }
             Tab::Recurring => {
                 if let Some(lines) = self.lines {
-                    recurring::render(ui, lines)
+                    recurring::render(ui, lines, self.settings)
+                } else {
+                    vec![]
+                }
+            }
+            Tab::Balance => {
+                if let Some(lines) = self.lines {
+                    balance::render(ui, lines)
+                } else {
+                    vec![]
+                }
+            }
+            Tab::Budgets => budgets::render(ui, self.lines.as_ref(), self.settings),
+            Tab::YearOverYear => {
+                if let Some(lines) = self.lines {
+                    year_over_year::render(ui, lines)
+                } else {
+                    vec![]
+                }
+            }
+            Tab::PeriodComparison => {
+                if let Some(lines) = self.lines {
+                    period_comparison::render(ui, lines)
+                } else {
+                    vec![]
+                }
+            }
+            Tab::Merchants => {
+                if let Some(lines) = self.lines {
+                    merchants::render(ui, lines)
+                } else {
+                    vec![]
+                }
+            }
+            Tab::Calendar => {
+                if let Some(lines) = self.lines {
+                    calendar::render(ui, lines)
+                } else {
+                    vec![]
+                }
+            }
+            Tab::Anomalies => {
+                if let Some(lines) = self.lines {
+                    anomalies::render(ui, lines)
+                } else {
+                    vec![]
+                }
+            }
+            Tab::Breakdown => {
+                if let Some(lines) = self.lines {
+                    breakdown::render(ui, lines)
+                } else {
+                    vec![]
+                }
+            }
+            Tab::CashFlow => {
+                if let Some(lines) = self.lines {
+                    cash_flow::render(ui, lines)
                 } else {
                     vec![]
                 }
@@ -215,3 +499,26 @@ pub fn scale_buttons(ui: &mut egui::Ui) -> Vec<Effect> {
 
     effects
 }
+
+pub fn history_buttons(ui: &mut egui::Ui, history: &History) -> Vec<Effect> {
+    let mut effects = vec![];
+
+    ui.horizontal_wrapped(|ui| {
+        if ui
+            .add_enabled(history.can_undo(), egui::Button::new("⮪ Annuler"))
+            .on_hover_text(ui.ctx().format_shortcut(&UNDO_SHORTCUT))
+            .clicked()
+        {
+            effects.push(Effect::Undo);
+        }
+        if ui
+            .add_enabled(history.can_redo(), egui::Button::new("⮫ Rétablir"))
+            .on_hover_text(ui.ctx().format_shortcut(&REDO_SHORTCUT))
+            .clicked()
+        {
+            effects.push(Effect::Redo);
+        }
+    });
+
+    effects
+}
@@ -0,0 +1,65 @@
+use chrono::Datelike;
+use egui_plot::{Plot, uniform_grid_spacer};
+
+use crate::line::Lines;
+
+/// First month of the lines, numbered 0 on monthly plots x axis
+pub fn first_month(lines: &Lines) -> chrono::NaiveDate {
+    lines.lower_date().with_day(1).unwrap()
+}
+
+/// Month numbered `x` from the first month, if `x` stands for a whole month
+pub fn month_at(first_month: chrono::NaiveDate, x: f64) -> Option<chrono::NaiveDate> {
+    if x.fract() != 0.0 {
+        return None;
+    }
+    let months = chrono::Months::new(x.abs() as u32);
+    if x < 0.0 {
+        first_month.checked_sub_months(months)
+    } else {
+        first_month.checked_add_months(months)
+    }
+}
+
+/// "2025-03" like label of the month numbered `x`
+pub fn month_label(first_month: chrono::NaiveDate, x: f64) -> String {
+    month_at(first_month, x)
+        .map(|month| month.format("%Y-%m").to_string())
+        .unwrap_or_default()
+}
+
+/// Plot of monthly values, x being months numbered from the lines first month
+pub fn month_plot<'a>(id: impl std::hash::Hash, lines: &Lines) -> Plot<'a> {
+    let first_month = first_month(lines);
+    Plot::new(id)
+        .x_grid_spacer(uniform_grid_spacer(|_| [1.0, 3.0, 12.0]))
+        .x_axis_formatter(move |mark, _| month_label(first_month, mark.value))
+        .label_formatter(move |name, value| {
+            let month = month_label(first_month, value.x.round());
+            if name.is_empty() {
+                format!("{month}\n{:>.2}", value.y)
+            } else {
+                format!("{name}\n{month}\n{:>.2}", value.y)
+            }
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn month_labels() {
+        // Given
+        let first_month = chrono::NaiveDate::from_ymd_opt(2024, 11, 1).unwrap();
+
+        // When
+        let labels = [-1.0, 0.0, 2.0, 2.5].map(|x| month_label(first_month, x));
+
+        // Then
+        assert_eq!(
+            labels,
+            ["2024-10", "2024-11", "2025-01", ""].map(String::from)
+        );
+    }
+}
@@ -0,0 +1,110 @@
+use std::sync::Arc;
+
+use eframe::egui::{self, RichText};
+use egui_extras::DatePickerButton;
+
+use crate::{
+    compare::{PeriodComparison, by_largest_change, compare_periods, variation},
+    line::Lines,
+};
+
+use super::effect::Effect;
+
+type Period = (chrono::NaiveDate, chrono::NaiveDate);
+/// Compared periods and imported lines address
+type ComparisonKey = (Period, Period, usize);
+
+pub fn render(ui: &mut egui::Ui, lines: &Lines) -> Vec<Effect> {
+    let state_id = ui.id().with("period_comparison");
+    let (mut first, mut second, mut sorted) = ui.data(|d| {
+        d.get_temp::<(Period, Period, bool)>(state_id)
+            .unwrap_or_else(|| {
+                let middle = lines.lower_date() + (lines.higher_date() - lines.lower_date()) / 2;
+                (
+                    (lines.lower_date(), middle),
+                    (middle + chrono::Days::new(1), lines.higher_date()),
+                    false,
+                )
+            })
+    });
+
+    egui::Grid::new("periods").show(ui, |ui| {
+        ui.label("Période 1");
+        ui.add(DatePickerButton::new(&mut first.0).id_salt("first_start"));
+        ui.add(DatePickerButton::new(&mut first.1).id_salt("first_end"));
+        ui.end_row();
+        ui.label("Période 2");
+        ui.add(DatePickerButton::new(&mut second.0).id_salt("second_start"));
+        ui.add(DatePickerButton::new(&mut second.1).id_salt("second_end"));
+        ui.end_row();
+    });
+    ui.checkbox(&mut sorted, "Trier par plus grand écart");
+
+    ui.separator();
+
+    // Computed again only when compared periods or imported lines change
+    let cache_id = ui.id().with("period_comparison_cache");
+    let key = (first, second, Arc::as_ptr(lines.all_lines()) as usize);
+    let comparisons =
+        match ui.data(|d| d.get_temp::<(ComparisonKey, Arc<Vec<PeriodComparison>>)>(cache_id)) {
+            Some((cached_key, comparisons)) if cached_key == key => comparisons,
+            _ => {
+                let comparisons = Arc::new(compare_periods(lines, first, second));
+                ui.data_mut(|d| d.insert_temp(cache_id, (key, comparisons.clone())));
+                comparisons
+            }
+        };
+    let rows = if sorted {
+        by_largest_change(&comparisons)
+    } else {
+        comparisons.iter().collect()
+    };
+
+    egui::ScrollArea::vertical().show(ui, |ui| {
+        egui::Grid::new("period_comparison")
+            .striped(true)
+            .show(ui, |ui| {
+                ui.label(RichText::new("Categorie").strong());
+                ui.label(RichText::new("Sous catégorie").strong());
+                ui.label(RichText::new("Total 1").strong());
+                ui.label(RichText::new("Moyenne 1").strong());
+                ui.label(RichText::new("Total 2").strong());
+                ui.label(RichText::new("Moyenne 2").strong());
+                ui.label(RichText::new("Écart mensuel").strong());
+                ui.label(RichText::new("Écart %").strong());
+                ui.end_row();
+
+                for comparison in rows {
+                    row(ui, comparison);
+                }
+            });
+    });
+
+    ui.data_mut(|d| d.insert_temp(state_id, (first, second, sorted)));
+
+    vec![]
+}
+
+fn row(ui: &mut egui::Ui, comparison: &PeriodComparison) {
+    match comparison.sub_category() {
+        Some(sub_category) => {
+            ui.label(comparison.category());
+            ui.label(sub_category);
+        }
+        None => {
+            ui.label(RichText::new(comparison.category()).strong());
+            ui.label("");
+        }
+    }
+    ui.label(format!("{:>.2}", comparison.first_total()));
+    ui.label(format!("{:>.2}", comparison.first_average()));
+    ui.label(format!("{:>.2}", comparison.second_total()));
+    ui.label(format!("{:>.2}", comparison.second_average()));
+    ui.label(format!("{:>+.2}", comparison.delta()));
+    ui.label(
+        variation(comparison.first_average(), comparison.second_average())
+            .map(|v| format!("{:>+.1}%", v))
+            .unwrap_or_default(),
+    );
+    ui.end_row();
+}
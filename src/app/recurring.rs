@@ -1,47 +1,212 @@
-use eframe::egui;
+use eframe::egui::{self, RichText};
 
-use crate::line::Lines;
+use crate::{line::Lines, recurring::Decision};
 
-use super::effect::Effect;
+use super::{effect::Effect, settings::Settings, sparkline::sparkline};
 
-pub fn render<'a>(ui: &mut egui::Ui, lines: &mut Lines) -> Vec<Effect> {
+pub fn render(ui: &mut egui::Ui, lines: &Lines, settings: &Settings) -> Vec<Effect> {
+    let mut effects = vec![];
+
+    let mut recurring_months = lines.recurring_months();
+    if ui
+        .add(egui::Slider::new(&mut recurring_months, 1..=lines.months_count()).text("Mois"))
+        .changed()
+    {
+        effects.push(Effect::SetRecurringMonths(recurring_months));
+    };
+
+    let mut recurring_approx = lines.parameters.recurring_approx;
     if ui
-        .add(egui::Slider::new(
-            &mut lines.recurring_months,
-            1..=lines.months_count,
-        ))
+        .add(
+            egui::Slider::new(&mut recurring_approx, 0.0..=100.0)
+                .suffix("%")
+                .text("Tolérance montant"),
+        )
         .changed()
     {
-        return vec![Effect::RecomputeRecurring];
+        effects.push(Effect::SetRecurringApprox(recurring_approx));
     };
 
+    let mut recurring_similarity = lines.parameters.recurring_similarity;
     if ui
-        .add(egui::Slider::new(&mut lines.recurring_approx, 0.0..=100.0).suffix("%"))
+        .add(egui::Slider::new(&mut recurring_similarity, 0.0..=1.0).text("Similarité libellé"))
         .changed()
     {
-        return vec![Effect::RecomputeRecurring];
+        effects.push(Effect::SetRecurringSimilarity(recurring_similarity));
     };
 
     egui::Grid::new("lines").striped(true).show(ui, |ui| {
-        for line in lines.recurring() {
-            ui.label(line.date_raw());
-            ui.label(line.libelle_simplifie());
-            ui.label(line.libelle_operation());
-            ui.label(line.categorie());
-            ui.label(line.sous_categorie());
+        ui.label("");
+        ui.label(RichText::new("Bénéficiaire").strong());
+        ui.label(RichText::new("Categorie").strong());
+        ui.label(RichText::new("Sous catégorie").strong());
+        ui.label(RichText::new("Périodicité").strong());
+        ui.label(RichText::new("Occurrences").strong());
+        ui.label(RichText::new("Montant moyen").strong());
+        ui.label(RichText::new("Coût mensuel").strong());
+        ui.label(RichText::new("Première").strong());
+        ui.label(RichText::new("Dernière").strong());
+        ui.label(RichText::new("Prochaine").strong());
+        ui.label(RichText::new("Historique").strong());
+        ui.label("");
+        ui.end_row();
+
+        for series in lines.recurring() {
+            let line = series.line();
+            let expanded_id = ui
+                .id()
+                .with(("recurring_expanded", series.payee(), line.date_raw()));
+            let mut expanded = ui.data(|d| d.get_temp::<bool>(expanded_id).unwrap_or(false));
+
+            if ui.button(if expanded { "⏷" } else { "⏵" }).clicked() {
+                expanded = !expanded;
+                ui.data_mut(|d| d.insert_temp(expanded_id, expanded));
+            }
+            let decision = settings.recurring_decision(series.payee(), line.amount());
+            let pinned = decision == Some(Decision::Pinned);
             ui.label(
-                line.debit()
-                    .map(|v| format!("{:>.2}", v))
-                    .unwrap_or("".to_string()),
+                RichText::new(if pinned {
+                    format!("📌 {}", series.payee())
+                } else {
+                    series.payee().to_string()
+                })
+                .strong(),
             );
-            ui.label(
-                line.credit()
-                    .map(|v| format!("{:>.2}", v))
-                    .unwrap_or("".to_string()),
+            ui.label(line.categorie());
+            ui.label(line.sous_categorie());
+            ui.label(series.periodicity().to_string());
+            ui.label(series.occurrences().len().to_string());
+            ui.label(format!("{:>.2}", series.average_amount()));
+            ui.label(format!("{:>.2}", series.monthly_cost()));
+            ui.label(series.first_date().format("%d/%m/%Y").to_string());
+            ui.label(series.last_date().format("%d/%m/%Y").to_string());
+            ui.label(series.next_date().format("%d/%m/%Y").to_string());
+            sparkline(
+                ui,
+                (
+                    "recurring_sparkline",
+                    series.payee(),
+                    series.line().date_raw(),
+                ),
+                series
+                    .occurrences()
+                    .iter()
+                    .map(|l| {
+                        [
+                            (l.date() - series.first_date()).num_days() as f64,
+                            l.amount() as f64,
+                        ]
+                    })
+                    .collect(),
             );
+            ui.horizontal(|ui| {
+                if ui
+                    .selectable_label(pinned, "📌")
+                    .on_hover_text("Toujours considérer comme récurrent")
+                    .clicked()
+                {
+                    effects.push(Effect::SetRecurringDecision(
+                        series.payee().to_string(),
+                        line.amount(),
+                        (!pinned).then_some(Decision::Pinned),
+                    ));
+                }
+                if ui
+                    .button("✖")
+                    .on_hover_text("Ne plus considérer comme récurrent")
+                    .clicked()
+                {
+                    effects.push(Effect::SetRecurringDecision(
+                        series.payee().to_string(),
+                        line.amount(),
+                        Some(Decision::Dismissed),
+                    ));
+                }
+            });
             ui.end_row();
+
+            if expanded {
+                for occurrence in series.occurrences() {
+                    ui.label("");
+                    ui.label("");
+                    ui.label(occurrence.date_raw());
+                    ui.label(occurrence.libelle_operation());
+                    ui.label("");
+                    ui.label("");
+                    ui.label(format!("{:>.2}", occurrence.amount()));
+                    ui.end_row();
+                }
+            }
         }
     });
 
-    vec![]
+    ui.separator();
+
+    ui.collapsing("Séries écartées", |ui| {
+        egui::Grid::new("dismissed_recurring")
+            .striped(true)
+            .show(ui, |ui| {
+                for decision in settings
+                    .recurring_decisions
+                    .iter()
+                    .filter(|d| d.decision() == Decision::Dismissed)
+                {
+                    ui.label(decision.payee());
+                    ui.label(format!("{:>.2}", decision.amount()));
+                    if ui.button("Restaurer").clicked() {
+                        effects.push(Effect::SetRecurringDecision(
+                            decision.payee().to_string(),
+                            decision.amount(),
+                            None,
+                        ));
+                    }
+                    ui.end_row();
+                }
+            });
+    });
+
+    ui.collapsing("Changements de prix", |ui| {
+        let mut price_change_threshold = lines.parameters.price_change_threshold;
+        if ui
+            .add(
+                egui::Slider::new(&mut price_change_threshold, 0.0..=50.0)
+                    .suffix("%")
+                    .text("Seuil de variation"),
+            )
+            .changed()
+        {
+            effects.push(Effect::SetPriceChangeThreshold(price_change_threshold));
+        };
+
+        egui::Grid::new("price_changes")
+            .striped(true)
+            .show(ui, |ui| {
+                ui.label(RichText::new("Bénéficiaire").strong());
+                ui.label(RichText::new("Date").strong());
+                ui.label(RichText::new("Ancien montant").strong());
+                ui.label(RichText::new("Nouveau montant").strong());
+                ui.label(RichText::new("Variation").strong());
+                ui.label(RichText::new("Impact annuel").strong());
+                ui.end_row();
+
+                for price_change in lines.price_changes() {
+                    ui.label(price_change.payee());
+                    ui.label(price_change.date().format("%d/%m/%Y").to_string());
+                    ui.label(format!("{:>.2}", price_change.old_amount()));
+                    ui.label(format!("{:>.2}", price_change.new_amount()));
+                    ui.label(format!("{:>+.1}%", price_change.variation()));
+                    ui.label(format!("{:>.2}", price_change.yearly_impact()));
+                    ui.end_row();
+                }
+            });
+
+        let yearly_impact = lines
+            .price_changes()
+            .iter()
+            .map(|c| c.yearly_impact())
+            .sum::<f32>();
+        ui.label(RichText::new(format!("Impact annuel total : {:>.2}", yearly_impact)).strong());
+    });
+
+    effects
 }
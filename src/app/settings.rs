@@ -0,0 +1,163 @@
+use derive_more::Display;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    budget::Budget,
+    recurring::{Decision, RecurringDecision},
+};
+
+/// User preferences and decisions, saved between sessions.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub recurring_decisions: Vec<RecurringDecision>,
+    pub budgets: Vec<Budget>,
+    /// Budgets are envelopes: leftovers roll over to next months
+    pub envelope_mode: bool,
+    /// Categories histogram drawn as lines or stacked bars
+    pub histogram_mode: HistogramMode,
+}
+
+/// How the categories histogram is drawn
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize, Display)]
+pub enum HistogramMode {
+    #[default]
+    #[display("Courbes")]
+    Lines,
+    #[display("Barres empilées")]
+    StackedBars,
+}
+
+impl Settings {
+    pub fn recurring_decision(&self, payee: &str, amount: f32) -> Option<Decision> {
+        self.recurring_decisions
+            .iter()
+            .find(|d| d.payee() == payee && d.amount() == amount)
+            .map(|d| d.decision())
+    }
+
+    /// Set (or remove, with `None`) the decision about the given series
+    pub fn set_recurring_decision(
+        &mut self,
+        payee: String,
+        amount: f32,
+        decision: Option<Decision>,
+    ) {
+        self.recurring_decisions
+            .retain(|d| !(d.payee() == payee && d.amount() == amount));
+        if let Some(decision) = decision {
+            self.recurring_decisions
+                .push(RecurringDecision::new(payee, amount, decision));
+        }
+    }
+
+    pub fn budget(&self, category: &str, sub_category: Option<&str>) -> Option<&Budget> {
+        self.budgets
+            .iter()
+            .find(|b| b.is_for(category, sub_category))
+    }
+
+    /// Set (or remove, with `None`) the monthly budget of the given category
+    pub fn set_budget(
+        &mut self,
+        category: String,
+        sub_category: Option<String>,
+        amount: Option<f32>,
+    ) {
+        let Some(amount) = amount else {
+            self.budgets
+                .retain(|b| !b.is_for(&category, sub_category.as_deref()));
+            for budget in &mut self.budgets {
+                if budget.is_covered_by(&category, sub_category.as_deref()) {
+                    budget.set_covered_by(None);
+                }
+            }
+            return;
+        };
+
+        match self
+            .budgets
+            .iter_mut()
+            .find(|b| b.is_for(&category, sub_category.as_deref()))
+        {
+            Some(budget) => budget.set_amount(amount),
+            None => self
+                .budgets
+                .push(Budget::new(category, sub_category, amount, None, vec![])),
+        }
+    }
+
+    /// Set (or reset, with `None`) the amount assigned to the given envelope in a month
+    pub fn set_budget_assigned(
+        &mut self,
+        category: &str,
+        sub_category: Option<&str>,
+        month: chrono::NaiveDate,
+        amount: Option<f32>,
+    ) {
+        if let Some(budget) = self
+            .budgets
+            .iter_mut()
+            .find(|b| b.is_for(category, sub_category))
+        {
+            budget.set_assigned(month, amount);
+        }
+    }
+
+    /// Set the envelope taking the given envelope overspending
+    pub fn set_budget_cover(
+        &mut self,
+        category: &str,
+        sub_category: Option<&str>,
+        covered_by: Option<(String, Option<String>)>,
+    ) {
+        if let Some(budget) = self
+            .budgets
+            .iter_mut()
+            .find(|b| b.is_for(category, sub_category))
+        {
+            budget.set_covered_by(covered_by);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn set_budget_in_place() {
+        // Given
+        let mut settings = Settings::default();
+        settings.set_budget("Alimentation".to_string(), None, Some(-300.0));
+        settings.set_budget("Loisirs".to_string(), None, Some(-100.0));
+
+        // When
+        settings.set_budget("Alimentation".to_string(), None, Some(-350.0));
+
+        // Then
+        assert_eq!(
+            settings.budgets,
+            vec![
+                Budget::new("Alimentation".to_string(), None, -350.0, None, vec![]),
+                Budget::new("Loisirs".to_string(), None, -100.0, None, vec![]),
+            ]
+        );
+    }
+
+    #[test]
+    fn delete_covering_budget() {
+        // Given
+        let mut settings = Settings::default();
+        settings.set_budget("Alimentation".to_string(), None, Some(-300.0));
+        settings.set_budget("Loisirs".to_string(), None, Some(-100.0));
+        settings.set_budget_cover("Loisirs", None, Some(("Alimentation".to_string(), None)));
+
+        // When
+        settings.set_budget("Alimentation".to_string(), None, None);
+
+        // Then
+        assert_eq!(settings.budgets.len(), 1);
+        assert_eq!(settings.budgets[0].covered_by(), None);
+    }
+}
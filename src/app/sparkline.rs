@@ -0,0 +1,23 @@
+use std::hash::Hash;
+
+use eframe::egui;
+use egui_plot::{Line, Plot, PlotPoints};
+
+/// Small line plot without axes nor interactions, to fit in a table cell
+pub fn sparkline(ui: &mut egui::Ui, id: impl Hash, points: Vec<[f64; 2]>) {
+    Plot::new(id)
+        .width(120.0)
+        .height(24.0)
+        .show_axes(false)
+        .show_grid(false)
+        .show_background(false)
+        .allow_drag(false)
+        .allow_zoom(false)
+        .allow_scroll(false)
+        .allow_boxed_zoom(false)
+        .show_x(false)
+        .show_y(false)
+        .show(ui, |plot_ui| {
+            plot_ui.line(Line::new("", PlotPoints::from(points)));
+        });
+}
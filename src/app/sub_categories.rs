@@ -1,13 +1,20 @@
 use eframe::egui::{self, Frame, RichText, Vec2};
-use egui_plot::{Legend, Line, Plot, PlotPoints};
+use egui_plot::{Legend, Line, PlotPoints};
 
 use crate::{app::scale_buttons, line::Lines};
 
-use super::effect::Effect;
+use super::{
+    budgets::{budget_legend, month_cell},
+    categories::stats_cells,
+    effect::Effect,
+    month_axis::month_plot,
+    settings::Settings,
+};
 
-pub fn render(ui: &mut egui::Ui, lines: &mut Lines) -> Vec<Effect> {
+pub fn render(ui: &mut egui::Ui, lines: &mut Lines, settings: &Settings) -> Vec<Effect> {
     let mut effects = vec![];
     effects.extend(scale_buttons(ui));
+    budget_legend(ui, settings);
 
     for category in lines.categories() {
         ui.heading(category);
@@ -17,11 +24,18 @@ pub fn render(ui: &mut egui::Ui, lines: &mut Lines) -> Vec<Effect> {
             .show(ui, |ui| {
                 ui.label("");
                 ui.label(RichText::new("Categorie").strong());
-                for month in &lines.active_months {
+                for month in lines.active_months() {
                     ui.label(RichText::new(month.format("%Y-%m").to_string()).strong());
                 }
                 ui.label(RichText::new("Total").strong());
-                ui.label(RichText::new("Moyenne").strong());
+                ui.label(
+                    RichText::new(format!("Moyenne ({})", lines.parameters.average_mode)).strong(),
+                );
+                ui.label(RichText::new("Médiane").strong());
+                ui.label(RichText::new("Min").strong());
+                ui.label(RichText::new("Max").strong());
+                ui.label(RichText::new("Écart type").strong());
+                ui.label(RichText::new("Mois actifs").strong());
                 ui.end_row();
 
                 for (category_, sub_category, months, total, average) in
@@ -33,12 +47,14 @@ pub fn render(ui: &mut egui::Ui, lines: &mut Lines) -> Vec<Effect> {
                         }
                         ui.label(sub_category);
 
+                        let budget = settings.budget(category, Some(sub_category));
                         for month_total in months {
-                            ui.label(format!("{:>.2}", month_total));
+                            month_cell(ui, *month_total, budget);
                         }
 
                         ui.label(format!("{:>.2}", total));
                         ui.label(format!("{:>.2}", average));
+                        stats_cells(ui, months);
                         ui.end_row();
                     }
                 }
@@ -61,8 +77,9 @@ pub fn render(ui: &mut egui::Ui, lines: &mut Lines) -> Vec<Effect> {
         Frame::default().show(ui, |ui| {
             ui.collapsing(format!("Histogramme {category}"), |ui| {
                 ui.set_min_size(Vec2::new(ui.available_width(), 350.0));
-                let plot = Plot::new(format!("Historique {category}")).legend(Legend::default());
-                let x = plot.show(ui, |plot_ui| {
+                let plot =
+                    month_plot(format!("Historique {category}"), lines).legend(Legend::default());
+                let _ = plot.show(ui, |plot_ui| {
                     for (category_, sub_category, _, values) in lines.sous_categories_histogram() {
                         if category_ == category {
                             plot_ui.line(Line::new(sub_category, PlotPoints::from(values.clone())));
@@ -0,0 +1,144 @@
+use eframe::egui::{self, RichText};
+use egui_plot::{Legend, Line, Plot, PlotPoints};
+
+use crate::{
+    compare::{variation, year_months, years},
+    line::Lines,
+};
+
+use super::effect::Effect;
+
+const MONTHS: [&str; 12] = [
+    "Janvier",
+    "Février",
+    "Mars",
+    "Avril",
+    "Mai",
+    "Juin",
+    "Juillet",
+    "Août",
+    "Septembre",
+    "Octobre",
+    "Novembre",
+    "Décembre",
+];
+
+pub fn render(ui: &mut egui::Ui, lines: &Lines) -> Vec<Effect> {
+    let years = years(lines);
+    let Some(last_year) = years.last().copied() else {
+        return vec![];
+    };
+
+    let state_id = ui.id().with("year_over_year");
+    let (mut previous, mut current, mut month, mut chart_category) = ui.data(|d| {
+        d.get_temp::<(i32, i32, Option<usize>, Option<String>)>(state_id)
+            .unwrap_or((last_year - 1, last_year, None, None))
+    });
+
+    ui.horizontal(|ui| {
+        year_combo(ui, "yoy_previous", &mut previous, &years);
+        ui.label("comparée à");
+        year_combo(ui, "yoy_current", &mut current, &years);
+        egui::ComboBox::from_id_salt("yoy_month")
+            .selected_text(month.map(|m| MONTHS[m]).unwrap_or("Année entière"))
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut month, None, "Année entière");
+                for (i, name) in MONTHS.iter().enumerate() {
+                    ui.selectable_value(&mut month, Some(i), *name);
+                }
+            });
+    });
+
+    ui.separator();
+
+    let period_total = |values: [f32; 12]| match month {
+        Some(month) => values[month],
+        None => values.iter().sum(),
+    };
+
+    egui::Grid::new("year_over_year")
+        .striped(true)
+        .show(ui, |ui| {
+            ui.label("");
+            ui.label(RichText::new("Categorie").strong());
+            ui.label(RichText::new(previous.to_string()).strong());
+            ui.label(RichText::new(current.to_string()).strong());
+            ui.label(RichText::new("Écart").strong());
+            ui.label(RichText::new("Écart %").strong());
+            ui.end_row();
+
+            for (category, months, _, _) in lines.categories_totals() {
+                let old = period_total(year_months(lines, months, previous));
+                let new = period_total(year_months(lines, months, current));
+
+                if ui
+                    .selectable_label(chart_category.as_ref() == Some(category), "📈")
+                    .clicked()
+                {
+                    chart_category = Some(category.clone());
+                }
+                ui.label(category);
+                ui.label(format!("{:>.2}", old));
+                ui.label(format!("{:>.2}", new));
+                ui.label(format!("{:>+.2}", new - old));
+                ui.label(
+                    variation(old, new)
+                        .map(|v| format!("{:>+.1}%", v))
+                        .unwrap_or_default(),
+                );
+                ui.end_row();
+            }
+        });
+
+    ui.separator();
+
+    let chart_months = match &chart_category {
+        Some(category) => lines
+            .categories_totals()
+            .iter()
+            .find(|(category_, _, _, _)| category_ == category)
+            .map(|(_, months, _, _)| months.clone()),
+        None => Some(lines.months_sums().iter().map(|(_, v)| *v).collect()),
+    };
+    ui.label(chart_category.as_deref().unwrap_or("Toutes catégories"));
+    if let Some(chart_months) = chart_months {
+        Plot::new("year_over_year_chart")
+            .legend(Legend::default())
+            .height(240.0)
+            .x_axis_formatter(|mark, _| {
+                MONTHS
+                    .get(mark.value.round() as usize)
+                    .filter(|_| mark.value >= 0.0)
+                    .map(|m| m.to_string())
+                    .unwrap_or_default()
+            })
+            .show(ui, |plot_ui| {
+                for year in [previous, current] {
+                    plot_ui.line(Line::new(
+                        year.to_string(),
+                        PlotPoints::from(
+                            year_months(lines, &chart_months, year)
+                                .iter()
+                                .enumerate()
+                                .map(|(i, v)| [i as f64, *v as f64])
+                                .collect::<Vec<[f64; 2]>>(),
+                        ),
+                    ));
+                }
+            });
+    }
+
+    ui.data_mut(|d| d.insert_temp(state_id, (previous, current, month, chart_category)));
+
+    vec![]
+}
+
+fn year_combo(ui: &mut egui::Ui, id: &str, selected: &mut i32, years: &[i32]) {
+    egui::ComboBox::from_id_salt(id)
+        .selected_text(selected.to_string())
+        .show_ui(ui, |ui| {
+            for year in years {
+                ui.selectable_value(selected, *year, year.to_string());
+            }
+        });
+}
@@ -0,0 +1,196 @@
+use derive_more::Constructor;
+use itertools::Itertools;
+
+use crate::{extract::EXCLUDED_CATEGORY, line::Lines};
+
+/// Part of the period expenses spent in a category or sub-category.
+#[derive(Debug, Clone, PartialEq, Constructor)]
+pub struct Share {
+    label: String,
+    /// Spent amount, as a positive value
+    amount: f32,
+    /// Part of the total expenses, between 0 and 1
+    ratio: f32,
+}
+
+impl Share {
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    pub fn amount(&self) -> f32 {
+        self.amount
+    }
+
+    pub fn ratio(&self) -> f32 {
+        self.ratio
+    }
+}
+
+/// Income flowing to expenses and savings over the period.
+#[derive(Debug, Clone, PartialEq, Constructor)]
+pub struct CashFlow {
+    /// Income categories
+    incomes: Vec<Share>,
+    /// Expense categories, with their sub-categories
+    expenses: Vec<(Share, Vec<Share>)>,
+    /// Income left once expenses paid, negative when expenses exceed income
+    savings: f32,
+}
+
+impl CashFlow {
+    pub fn incomes(&self) -> &[Share] {
+        &self.incomes
+    }
+
+    pub fn expenses(&self) -> &[(Share, Vec<Share>)] {
+        &self.expenses
+    }
+
+    pub fn savings(&self) -> f32 {
+        self.savings
+    }
+
+    /// Amount flowing through the budget: income, plus savings used to cover expenses
+    pub fn total(&self) -> f32 {
+        self.incomes.iter().map(Share::amount).sum::<f32>() + (-self.savings).max(0.0)
+    }
+}
+
+/// Expenses shares of categories, largest first
+pub fn category_shares(lines: &Lines) -> Vec<Share> {
+    shares(category_totals(lines), false)
+}
+
+/// Income shares of categories, largest first
+pub fn income_shares(lines: &Lines) -> Vec<Share> {
+    shares(category_totals(lines), true)
+}
+
+/// Expenses shares of the given category sub-categories, largest first
+pub fn sub_category_shares(lines: &Lines, category: &str) -> Vec<Share> {
+    shares(
+        lines
+            .sub_categories_total()
+            .iter()
+            .filter(|(c, ..)| c == category)
+            .map(|(_, sub_category, _, total, _)| (sub_category.as_str(), *total)),
+        false,
+    )
+}
+
+/// Period income split into expense categories and sub-categories, and savings
+pub fn cash_flow(lines: &Lines) -> CashFlow {
+    let incomes = income_shares(lines);
+    let expenses = category_shares(lines);
+    let savings = incomes.iter().map(Share::amount).sum::<f32>()
+        - expenses.iter().map(Share::amount).sum::<f32>();
+    let expenses = expenses
+        .into_iter()
+        .map(|category| {
+            let sub_categories = sub_category_shares(lines, category.label());
+            (category, sub_categories)
+        })
+        .collect();
+    CashFlow::new(incomes, expenses, savings)
+}
+
+fn category_totals(lines: &Lines) -> impl Iterator<Item = (&str, f32)> {
+    lines
+        .categories_totals()
+        .iter()
+        .filter(|(category, ..)| category != EXCLUDED_CATEGORY)
+        .map(|(category, _, total, _)| (category.as_str(), *total))
+}
+
+fn shares<'a>(totals: impl Iterator<Item = (&'a str, f32)>, income: bool) -> Vec<Share> {
+    let amounts = totals
+        .filter(|(_, total)| if income { *total > 0.0 } else { *total < 0.0 })
+        .map(|(label, total)| (label, total.abs()))
+        .sorted_by(|(_, a), (_, b)| b.total_cmp(a))
+        .collect::<Vec<_>>();
+    let sum = amounts.iter().map(|(_, amount)| amount).sum::<f32>();
+    amounts
+        .into_iter()
+        .map(|(label, amount)| Share::new(label.to_string(), amount, amount / sum))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extract::TryIntoLines;
+
+    #[test]
+    fn expenses_shares() {
+        // Given
+        let raw = "header
+05/01/2024;SALAIRE;SALAIRE;;;Virement;Revenus;Salaires;;2000;05/01/2024;05/01/2024;0
+06/01/2024;CB SUPER;CB SUPER;;;Carte bancaire;Alimentation;Hyper/supermarche;-60;;06/01/2024;06/01/2024;0
+07/01/2024;CB BOULANGERIE;CB BOULANGERIE;;;Carte bancaire;Alimentation;Boulangerie;-15;;07/01/2024;07/01/2024;0
+08/01/2024;CINEMA;CINEMA;;;Carte bancaire;Loisirs;Cinema;-25;;08/01/2024;08/01/2024;0
+09/01/2024;VIR EPARGNE;VIR EPARGNE;;;Virement;Transaction exclue;Virement interne;-500;;09/01/2024;09/01/2024;0"
+            .to_string();
+        let lines = raw.into_lines("test".to_string()).unwrap();
+
+        // When
+        let categories = category_shares(&lines);
+        let sub_categories = sub_category_shares(&lines, "Alimentation");
+
+        // Then
+        assert_eq!(
+            categories,
+            vec![
+                Share::new("Alimentation".to_string(), 75.0, 0.75),
+                Share::new("Loisirs".to_string(), 25.0, 0.25),
+            ]
+        );
+        assert_eq!(
+            sub_categories,
+            vec![
+                Share::new("Hyper/supermarche".to_string(), 60.0, 0.8),
+                Share::new("Boulangerie".to_string(), 15.0, 0.2),
+            ]
+        );
+    }
+
+    #[test]
+    fn income_to_expenses_and_savings() {
+        // Given
+        let raw = "header
+05/01/2024;SALAIRE;SALAIRE;;;Virement;Revenus;Salaires;;1500;05/01/2024;05/01/2024;0
+06/01/2024;CAF;CAF;;;Virement;Allocations;Famille;;500;06/01/2024;06/01/2024;0
+07/01/2024;CB SUPER;CB SUPER;;;Carte bancaire;Alimentation;Hyper/supermarche;-300;;07/01/2024;07/01/2024;0
+08/01/2024;LOYER;LOYER;;;Prelevement;Logement;Loyer;-900;;08/01/2024;08/01/2024;0
+09/01/2024;VIR EPARGNE;VIR EPARGNE;;;Virement;Transaction exclue;Virement interne;-500;;09/01/2024;09/01/2024;0"
+            .to_string();
+        let lines = raw.into_lines("test".to_string()).unwrap();
+
+        // When
+        let cash_flow = cash_flow(&lines);
+
+        // Then
+        assert_eq!(
+            cash_flow.incomes(),
+            &[
+                Share::new("Revenus".to_string(), 1500.0, 0.75),
+                Share::new("Allocations".to_string(), 500.0, 0.25),
+            ]
+        );
+        assert_eq!(
+            cash_flow.expenses(),
+            &[
+                (
+                    Share::new("Logement".to_string(), 900.0, 0.75),
+                    vec![Share::new("Loyer".to_string(), 900.0, 1.0)]
+                ),
+                (
+                    Share::new("Alimentation".to_string(), 300.0, 0.25),
+                    vec![Share::new("Hyper/supermarche".to_string(), 300.0, 1.0)]
+                ),
+            ]
+        );
+        assert_eq!(cash_flow.savings(), 800.0);
+        assert_eq!(cash_flow.total(), 2000.0);
+    }
+}
@@ -0,0 +1,276 @@
+use chrono::Datelike;
+use derive_more::Constructor;
+use serde::{Deserialize, Serialize};
+
+use crate::{extract::EXCLUDED_CATEGORY, line::Lines};
+
+/// Planned monthly amount of a category, or of one of its sub-categories. Amounts
+/// follow lines sign: expenses budgets are negative.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Constructor)]
+pub struct Budget {
+    category: String,
+    sub_category: Option<String>,
+    amount: f32,
+    /// Envelope (category and sub-category) taking this envelope overspending
+    #[serde(default)]
+    covered_by: Option<(String, Option<String>)>,
+    /// Envelope amounts assigned in given months ("2025-03" like), instead of the
+    /// monthly budget
+    #[serde(default)]
+    assignments: Vec<(String, f32)>,
+}
+
+impl Budget {
+    pub fn category(&self) -> &str {
+        &self.category
+    }
+
+    pub fn sub_category(&self) -> Option<&str> {
+        self.sub_category.as_deref()
+    }
+
+    pub fn amount(&self) -> f32 {
+        self.amount
+    }
+
+    pub fn set_amount(&mut self, amount: f32) {
+        self.amount = amount;
+    }
+
+    pub fn covered_by(&self) -> Option<&(String, Option<String>)> {
+        self.covered_by.as_ref()
+    }
+
+    pub fn set_covered_by(&mut self, covered_by: Option<(String, Option<String>)>) {
+        self.covered_by = covered_by;
+    }
+
+    /// Amount assigned to the envelope in the given month, by default the monthly
+    /// budget
+    pub fn assigned(&self, month: chrono::NaiveDate) -> f32 {
+        let month = month_key(month);
+        self.assignments
+            .iter()
+            .find(|(month_, _)| *month_ == month)
+            .map(|(_, amount)| *amount)
+            .unwrap_or(-self.amount)
+    }
+
+    pub fn is_assigned(&self, month: chrono::NaiveDate) -> bool {
+        let month = month_key(month);
+        self.assignments.iter().any(|(month_, _)| *month_ == month)
+    }
+
+    /// Set (or reset to the monthly budget, with `None`) the amount assigned in the
+    /// given month
+    pub fn set_assigned(&mut self, month: chrono::NaiveDate, amount: Option<f32>) {
+        let month = month_key(month);
+        match (
+            self.assignments
+                .iter_mut()
+                .find(|(month_, _)| *month_ == month),
+            amount,
+        ) {
+            (Some(assignment), Some(amount)) => assignment.1 = amount,
+            (None, Some(amount)) => self.assignments.push((month, amount)),
+            (_, None) => self.assignments.retain(|(month_, _)| *month_ != month),
+        }
+    }
+
+    /// Month amounts of the budget category (or sub-category), by month of
+    /// [`Lines::all_months`]
+    pub fn actuals<'a>(&self, lines: &'a Lines) -> Option<&'a [f32]> {
+        match &self.sub_category {
+            None => lines
+                .categories_totals()
+                .iter()
+                .find(|(category, _, _, _)| category == &self.category)
+                .map(|(_, months, _, _)| months.as_slice()),
+            Some(sub_category) => lines
+                .sub_categories_total()
+                .iter()
+                .find(|(category, sub_category_, _, _, _)| {
+                    category == &self.category && sub_category_ == sub_category
+                })
+                .map(|(_, _, months, _, _)| months.as_slice()),
+        }
+    }
+
+    /// Overspending goes to the given envelope
+    pub fn is_covered_by(&self, category: &str, sub_category: Option<&str>) -> bool {
+        self.covered_by
+            .as_ref()
+            .is_some_and(|(c, s)| c == category && s.as_deref() == sub_category)
+    }
+
+    pub fn is_for(&self, category: &str, sub_category: Option<&str>) -> bool {
+        self.category == category && self.sub_category.as_deref() == sub_category
+    }
+
+    /// Amount left to spend (or to receive) given the actual month amount
+    pub fn remaining(&self, actual: f32) -> f32 {
+        actual - self.amount
+    }
+
+    /// Actual amount is below the planned one (more spent or less received)
+    pub fn is_overspent(&self, actual: f32) -> bool {
+        self.remaining(actual) < 0.0
+    }
+}
+
+fn month_key(month: chrono::NaiveDate) -> String {
+    month.format("%Y-%m").to_string()
+}
+
+/// Income of each month of [`Lines::all_months`], to be assigned to envelopes.
+pub fn months_income(lines: &Lines) -> Vec<f32> {
+    lines
+        .all_months()
+        .iter()
+        .map(|month| {
+            lines
+                .lines()
+                .iter()
+                .filter(|l| {
+                    l.date().year() == month.year()
+                        && l.date().month() == month.month()
+                        && l.categorie() != EXCLUDED_CATEGORY
+                        && l.amount() > 0.0
+                })
+                .map(|l| l.amount())
+                .sum()
+        })
+        .collect()
+}
+
+/// Balance of each envelope (budget) at the end of each month of
+/// [`Lines::all_months`]. Leftovers roll over to the next month, overspending is
+/// taken from the covering envelope when there is one.
+pub fn envelope_balances(lines: &Lines, budgets: &[Budget]) -> Vec<Vec<f32>> {
+    let months_count = lines.all_months().len();
+    let actuals = budgets
+        .iter()
+        .map(|b| {
+            b.actuals(lines)
+                .map(|a| a.to_vec())
+                .unwrap_or_else(|| vec![0.0; months_count])
+        })
+        .collect::<Vec<Vec<f32>>>();
+    let covers = budgets
+        .iter()
+        .map(|b| {
+            b.covered_by().and_then(|(category, sub_category)| {
+                budgets
+                    .iter()
+                    .position(|c| c.is_for(category, sub_category.as_deref()))
+            })
+        })
+        .collect::<Vec<Option<usize>>>();
+    let assigned = budgets
+        .iter()
+        .map(|b| {
+            lines
+                .all_months()
+                .iter()
+                .map(|month| b.assigned(*month))
+                .collect()
+        })
+        .collect::<Vec<Vec<f32>>>();
+
+    rollover(&assigned, &actuals, &covers, months_count)
+}
+
+fn rollover(
+    assigned: &[Vec<f32>],
+    actuals: &[Vec<f32>],
+    covers: &[Option<usize>],
+    months_count: usize,
+) -> Vec<Vec<f32>> {
+    let mut balances = vec![Vec::with_capacity(months_count); assigned.len()];
+    let mut current = vec![0.0; assigned.len()];
+
+    for month in 0..months_count {
+        for ((balance, assigned), actuals) in current.iter_mut().zip(assigned).zip(actuals) {
+            *balance += assigned[month] + actuals[month];
+        }
+        for i in 0..current.len() {
+            if current[i] < 0.0
+                && let Some(cover) = covers[i]
+                && cover != i
+            {
+                current[cover] += current[i];
+                current[i] = 0.0;
+            }
+        }
+        for (i, balance) in current.iter().enumerate() {
+            balances[i].push(*balance);
+        }
+    }
+
+    balances
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn budget_remaining() {
+        // Given
+        let groceries = Budget::new("Alimentation".to_string(), None, -300.0, None, vec![]);
+        let salary = Budget::new(
+            "Revenus".to_string(),
+            Some("Salaire".to_string()),
+            2000.0,
+            None,
+            vec![],
+        );
+
+        // When/Then
+        assert_eq!(groceries.remaining(-250.0), -250.0 + 300.0);
+        assert!(!groceries.is_overspent(-250.0));
+        assert!(groceries.is_overspent(-320.0));
+        assert!(salary.is_overspent(1800.0));
+        assert!(!salary.is_overspent(2000.0));
+        assert!(salary.is_for("Revenus", Some("Salaire")));
+        assert!(!salary.is_for("Revenus", None));
+    }
+
+    #[test]
+    fn envelopes_rollover_and_cover() {
+        // Given
+        let assigned = [vec![100.0; 3], vec![50.0, 50.0, 50.0]];
+        let actuals = vec![vec![-40.0, -200.0, 0.0], vec![0.0, 0.0, -10.0]];
+        let covers = [Some(1), None];
+
+        // When
+        let balances = rollover(&assigned, &actuals, &covers, 3);
+
+        // Then
+        assert_eq!(balances[0], vec![60.0, 0.0, 100.0]);
+        assert_eq!(balances[1], vec![50.0, 60.0, 100.0]);
+    }
+
+    #[test]
+    fn envelope_month_assignment() {
+        // Given
+        let mut budget = Budget::new("Loisirs".to_string(), None, -100.0, None, vec![]);
+        let march = chrono::NaiveDate::from_ymd_opt(2025, 3, 1).unwrap();
+        let april = chrono::NaiveDate::from_ymd_opt(2025, 4, 1).unwrap();
+
+        // When
+        budget.set_assigned(march, Some(250.0));
+
+        // Then
+        assert_eq!(budget.assigned(march), 250.0);
+        assert_eq!(budget.assigned(april), 100.0);
+        assert!(budget.is_assigned(march));
+
+        // When
+        budget.set_assigned(march, None);
+
+        // Then
+        assert_eq!(budget.assigned(march), 100.0);
+        assert!(!budget.is_assigned(march));
+    }
+}
@@ -0,0 +1,75 @@
+use std::collections::HashMap;
+
+use chrono::Datelike;
+
+use crate::{extract::EXCLUDED_CATEGORY, line::Lines};
+
+/// Spending (positive amount) of each day of the lines period.
+pub fn daily_spending(lines: &Lines) -> Vec<(chrono::NaiveDate, f32)> {
+    let mut by_day: HashMap<chrono::NaiveDate, f32> = HashMap::new();
+    for line in lines
+        .lines()
+        .iter()
+        .filter(|l| l.categorie() != EXCLUDED_CATEGORY)
+    {
+        *by_day.entry(line.date()).or_default() += -line.amount().min(0.0);
+    }
+
+    lines
+        .lower_date()
+        .iter_days()
+        .take_while(|day| *day <= lines.higher_date())
+        .map(|day| (day, by_day.get(&day).copied().unwrap_or_default()))
+        .collect()
+}
+
+/// Spending totals by weekday, Monday first
+pub fn weekday_spending(daily: &[(chrono::NaiveDate, f32)]) -> [f32; 7] {
+    let mut totals = [0.0; 7];
+    for (day, spending) in daily {
+        totals[day.weekday().num_days_from_monday() as usize] += spending;
+    }
+    totals
+}
+
+/// Spending totals by day of month, first day first
+pub fn month_day_spending(daily: &[(chrono::NaiveDate, f32)]) -> [f32; 31] {
+    let mut totals = [0.0; 31];
+    for (day, spending) in daily {
+        totals[day.day0() as usize] += spending;
+    }
+    totals
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extract::TryIntoLines;
+
+    #[test]
+    fn spending_by_day() {
+        // Given
+        let raw = "header
+01/07/2024;SUPER;SUPER;;;Carte bancaire;Alimentation;Hyper/supermarche;-60;;01/07/2024;01/07/2024;0
+01/07/2024;SALAIRE;SALAIRE;;;Virement recu;Revenus;Salaire;;2000;01/07/2024;01/07/2024;0
+03/07/2024;CINEMA;CINEMA;;;Carte bancaire;Loisirs;Cinema;-12;;03/07/2024;03/07/2024;0
+08/07/2024;SUPER;SUPER;;;Carte bancaire;Alimentation;Hyper/supermarche;-30;;08/07/2024;08/07/2024;0"
+            .to_string();
+        let lines = raw.into_lines("test".to_string()).unwrap();
+
+        // When
+        let daily = daily_spending(&lines);
+
+        // Then
+        assert_eq!(daily.len(), 8);
+        assert_eq!(daily[0].1, 60.0);
+        assert_eq!(daily[1].1, 0.0);
+        assert_eq!(daily[2].1, 12.0);
+        // 1st and 8th of July 2024 are mondays
+        assert_eq!(
+            weekday_spending(&daily),
+            [90.0, 0.0, 12.0, 0.0, 0.0, 0.0, 0.0]
+        );
+        assert_eq!(month_day_spending(&daily)[7], 30.0);
+    }
+}
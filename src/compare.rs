@@ -0,0 +1,242 @@
+use chrono::Datelike;
+use derive_more::Constructor;
+use itertools::Itertools;
+
+use crate::line::{Line, Lines};
+
+/// Totals of a category (or of one of its sub-categories) over two periods.
+#[derive(Debug, Clone, PartialEq, Constructor)]
+pub struct PeriodComparison {
+    category: String,
+    sub_category: Option<String>,
+    first_total: f32,
+    first_average: f32,
+    second_total: f32,
+    second_average: f32,
+}
+
+impl PeriodComparison {
+    pub fn category(&self) -> &str {
+        &self.category
+    }
+
+    pub fn sub_category(&self) -> Option<&str> {
+        self.sub_category.as_deref()
+    }
+
+    pub fn first_total(&self) -> f32 {
+        self.first_total
+    }
+
+    pub fn first_average(&self) -> f32 {
+        self.first_average
+    }
+
+    pub fn second_total(&self) -> f32 {
+        self.second_total
+    }
+
+    pub fn second_average(&self) -> f32 {
+        self.second_average
+    }
+
+    /// Monthly average difference, periods may have different lengths
+    pub fn delta(&self) -> f32 {
+        self.second_average - self.first_average
+    }
+}
+
+/// Amounts of each month (January first) of the given year, from amounts by month
+/// of [`Lines::all_months`].
+pub fn year_months(lines: &Lines, months: &[f32], year: i32) -> [f32; 12] {
+    let mut values = [0.0; 12];
+    for (month, value) in lines.all_months().iter().zip(months) {
+        if month.year() == year {
+            values[month.month0() as usize] = *value;
+        }
+    }
+    values
+}
+
+/// Years covered by lines
+pub fn years(lines: &Lines) -> Vec<i32> {
+    let mut years = lines
+        .all_months()
+        .iter()
+        .map(|m| m.year())
+        .collect::<Vec<i32>>();
+    years.dedup();
+    years
+}
+
+/// Variation (in percent) from `old` to `new` absolute amounts, if `old` is not zero
+pub fn variation(old: f32, new: f32) -> Option<f32> {
+    (old != 0.0).then(|| (new.abs() - old.abs()) / old.abs() * 100.0)
+}
+
+/// Number of months, even partial, covered by the given dates range
+pub fn months_count(start: chrono::NaiveDate, end: chrono::NaiveDate) -> u32 {
+    let months = (end.year() - start.year()) * 12 + end.month() as i32 - start.month() as i32 + 1;
+    months.max(1) as u32
+}
+
+/// Categories, then their sub-categories, totals and monthly averages over the two
+/// given (inclusive) dates ranges, from all imported lines whatever the date range.
+pub fn compare_periods(
+    lines: &Lines,
+    first: (chrono::NaiveDate, chrono::NaiveDate),
+    second: (chrono::NaiveDate, chrono::NaiveDate),
+) -> Vec<PeriodComparison> {
+    let in_range = |line: &Line, range: (chrono::NaiveDate, chrono::NaiveDate)| {
+        line.date() >= range.0 && line.date() <= range.1
+    };
+    let compared = lines
+        .all_lines()
+        .iter()
+        .filter(|l| in_range(l, first) || in_range(l, second))
+        .collect::<Vec<&Line>>();
+    let total = |range: (chrono::NaiveDate, chrono::NaiveDate), filter: &dyn Fn(&Line) -> bool| {
+        let total = compared
+            .iter()
+            .filter(|l| in_range(l, range) && filter(l))
+            .map(|l| l.amount())
+            .sum::<f32>();
+        (total, total / months_count(range.0, range.1) as f32)
+    };
+    let comparison = |category: &str, sub_category: Option<&str>| {
+        let filter = |l: &Line| {
+            l.categorie() == category && sub_category.is_none_or(|s| l.sous_categorie() == s)
+        };
+        let (first_total, first_average) = total(first, &filter);
+        let (second_total, second_average) = total(second, &filter);
+        PeriodComparison::new(
+            category.to_string(),
+            sub_category.map(|s| s.to_string()),
+            first_total,
+            first_average,
+            second_total,
+            second_average,
+        )
+    };
+
+    let categories = compared
+        .iter()
+        .map(|l| l.categorie())
+        .sorted()
+        .dedup()
+        .collect::<Vec<&str>>();
+    let sub_categories = compared
+        .iter()
+        .map(|l| (l.categorie(), l.sous_categorie()))
+        .sorted()
+        .dedup()
+        .collect::<Vec<(&str, &str)>>();
+    categories
+        .into_iter()
+        .map(|category| comparison(category, None))
+        .chain(
+            sub_categories
+                .into_iter()
+                .map(|(category, sub_category)| comparison(category, Some(sub_category))),
+        )
+        .collect()
+}
+
+/// Comparisons sorted by largest monthly average change first
+pub fn by_largest_change(comparisons: &[PeriodComparison]) -> Vec<&PeriodComparison> {
+    comparisons
+        .iter()
+        .sorted_by(|a, b| b.delta().abs().total_cmp(&a.delta().abs()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        extract::{TryIntoLines, with_range},
+        line::DateRange,
+    };
+
+    #[test]
+    fn compare_years() {
+        // Given
+        let raw = "header
+05/11/2023;EAU;EAU;;;Prelevement;Logement;Eau;-40;;05/11/2023;05/11/2023;0
+05/02/2024;EAU;EAU;;;Prelevement;Logement;Eau;-50;;05/02/2024;05/02/2024;0"
+            .to_string();
+        let lines = raw.into_lines("test".to_string()).unwrap();
+        let (_, months, _, _) = &lines.categories_totals()[0];
+
+        // When
+        let previous = year_months(&lines, months, 2023);
+        let current = year_months(&lines, months, 2024);
+
+        // Then
+        assert_eq!(years(&lines), vec![2023, 2024]);
+        assert_eq!(previous[10], -40.0);
+        assert_eq!(current[1], -50.0);
+        assert_eq!(previous.iter().sum::<f32>(), -40.0);
+        assert_eq!(variation(-40.0, -50.0), Some(25.0));
+        assert_eq!(variation(0.0, -50.0), None);
+    }
+
+    #[test]
+    fn compare_two_periods() {
+        // Given
+        let raw = "header
+05/01/2024;LOYER;LOYER;;;Prelevement;Logement;Loyer;-500;;05/01/2024;05/01/2024;0
+05/02/2024;LOYER;LOYER;;;Prelevement;Logement;Loyer;-500;;05/02/2024;05/02/2024;0
+05/03/2024;LOYER;LOYER;;;Prelevement;Logement;Loyer;-800;;05/03/2024;05/03/2024;0
+06/03/2024;EAU;EAU;;;Prelevement;Logement;Eau;-30;;06/03/2024;06/03/2024;0
+07/03/2024;CINEMA;CINEMA;;;Carte bancaire;Loisirs;Cinema;-20;;07/03/2024;07/03/2024;0"
+            .to_string();
+        let lines = raw.into_lines("test".to_string()).unwrap();
+        let date = |month, day| chrono::NaiveDate::from_ymd_opt(2024, month, day).unwrap();
+
+        // When
+        let comparisons =
+            compare_periods(&lines, (date(1, 1), date(2, 29)), (date(3, 1), date(3, 31)));
+
+        // Then
+        let housing = comparisons
+            .iter()
+            .find(|c| c.category() == "Logement" && c.sub_category().is_none())
+            .unwrap();
+        assert_eq!(housing.first_total(), -1000.0);
+        assert_eq!(housing.first_average(), -500.0);
+        assert_eq!(housing.second_total(), -830.0);
+        assert_eq!(housing.delta(), -330.0);
+
+        let sorted = by_largest_change(&comparisons);
+        assert_eq!(
+            sorted
+                .iter()
+                .take(2)
+                .map(|c| (c.category(), c.sub_category()))
+                .collect::<Vec<_>>(),
+            vec![("Logement", None), ("Logement", Some("Loyer"))]
+        );
+        assert_eq!(months_count(date(1, 15), date(3, 2)), 3);
+    }
+
+    #[test]
+    fn compare_periods_outside_date_range() {
+        // Given
+        let raw = "header
+05/01/2024;LOYER;LOYER;;;Prelevement;Logement;Loyer;-500;;05/01/2024;05/01/2024;0
+05/03/2024;LOYER;LOYER;;;Prelevement;Logement;Loyer;-800;;05/03/2024;05/03/2024;0"
+            .to_string();
+        let lines = raw.into_lines("test".to_string()).unwrap();
+        let lines = with_range(&lines, DateRange::ThisMonth, &[]);
+        let date = |month, day| chrono::NaiveDate::from_ymd_opt(2024, month, day).unwrap();
+
+        // When
+        let comparisons =
+            compare_periods(&lines, (date(1, 1), date(1, 31)), (date(3, 1), date(3, 31)));
+
+        // Then
+        assert_eq!(comparisons[0].first_total(), -500.0);
+        assert_eq!(comparisons[0].second_total(), -800.0);
+    }
+}
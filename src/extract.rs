@@ -1,10 +1,26 @@
-use std::collections::HashSet;
+use std::{collections::HashMap, ops::RangeInclusive, sync::Arc};
 
 use chrono::Datelike;
 use itertools::Itertools;
 use thiserror::Error;
 
-use crate::line::{Line, Lines};
+use crate::{
+    anomaly::{line_anomalies, month_anomalies},
+    calendar::daily_spending,
+    forecast::{BalanceEvent, MonthForecast},
+    line::{DateRange, Line, Lines, Parameters, Period, Recurring},
+    merchant::merchants,
+    recurring::{
+        Decision, MissedPayment, Periodicity, PriceChange, RecurringDecision, RecurringSeries,
+        median_gap, words_similarity,
+    },
+    savings::MonthFlows,
+};
+
+/// Category of lines not counted as income or expenses (like internal transfers).
+pub(crate) const EXCLUDED_CATEGORY: &str = "Transaction exclue";
+/// Delay (in days) for an expected recurring occurrence to be registered.
+const MISSED_PAYMENT_DELAY_DAYS: u64 = 5;
 
 pub trait TryIntoLines {
     fn into_lines(self, name: String) -> Result<Lines, TryIntoLinesError>;
@@ -41,407 +57,932 @@ impl TryIntoLines for String {
             ));
         }
 
-        let dates = lines
-            .iter()
-            .map(|l| {
-                let mut splitted = l.date_raw().split('/');
-                let day = splitted.next().unwrap().parse::<u32>().unwrap();
-                let month = splitted.next().unwrap().parse::<u32>().unwrap();
-                let year = splitted.next().unwrap().parse::<i32>().unwrap();
-                chrono::NaiveDate::from_ymd_opt(year, month, day).unwrap()
-            })
-            .collect::<Vec<chrono::NaiveDate>>();
-        let lower_date = dates
+        let mut lines = build_lines(name, Arc::new(lines), DateRange::All);
+        update_recurring(&mut lines, &[]);
+        Ok(lines)
+    }
+}
+
+/// Aggregates of lines in the given date range, without recurring series (see
+/// [`update_recurring`])
+pub fn build_lines(name: String, all_lines: Arc<Vec<Line>>, range: DateRange) -> Lines {
+    let (lower_date, higher_date) = {
+        let dates = all_lines
             .iter()
+            .map(|l| l.date())
             .sorted()
-            .collect::<Vec<&chrono::NaiveDate>>()
-            .first()
-            .copied()
-            .unwrap()
-            .clone();
-        let higher_date = dates
+            .collect::<Vec<_>>();
+        range.bounds(*dates.first().unwrap(), *dates.last().unwrap())
+    };
+
+    let mut months_sums = vec![];
+    let mut months = vec![];
+    let mut current_date =
+        chrono::NaiveDate::from_ymd_opt(lower_date.year(), lower_date.month(), 1).unwrap();
+    while current_date <= higher_date {
+        months.push(current_date);
+        current_date = current_date
+            .checked_add_months(chrono::Months::new(1))
+            .unwrap();
+    }
+    let active_months = months.clone();
+
+    let lines = all_lines
+        .iter()
+        .filter(|l| l.date() >= lower_date && l.date() <= higher_date)
+        .cloned()
+        .collect::<Vec<Line>>();
+    let min_active_month = active_months.iter().min().unwrap();
+    let max_active_month = active_months.iter().max().unwrap();
+
+    let categories: Vec<String> = lines
+        .iter()
+        .map(|l| l.categorie().to_string())
+        .collect::<Vec<String>>()
+        .into_iter()
+        .unique()
+        .collect();
+
+    let mut sub_categories: Vec<(String, String)> = vec![];
+    for category in &categories {
+        for sub_category in lines
             .iter()
-            .sorted()
-            .collect::<Vec<&chrono::NaiveDate>>()
-            .last()
-            .copied()
-            .unwrap()
-            .clone();
+            .filter(|l| l.categorie() == category)
+            .map(|l| l.sous_categorie().to_string())
+            .collect::<Vec<String>>()
+            .into_iter()
+            .unique()
+            .collect::<Vec<String>>()
+        {
+            sub_categories.push((category.clone(), sub_category))
+        }
+    }
+
+    let active_months_count = months_between(min_active_month, max_active_month) + 1;
+    let mut categories_totals = vec![];
+    for category in &categories {
+        let category_lines = lines
+            .iter()
+            .filter(|l| l.categorie() == category)
+            .collect::<Vec<&Line>>();
+        let sum = category_lines
+            .iter()
+            .map(|l| l.credit().unwrap_or_default() + l.debit().unwrap_or_default())
+            .sum();
+        let average = sum / active_months_count as f32;
 
         let mut months_sums = vec![];
-        let mut months = vec![];
         let mut current_date =
             chrono::NaiveDate::from_ymd_opt(lower_date.year(), lower_date.month(), 1).unwrap();
         while current_date <= higher_date {
-            let month_total = lines
+            let category_month_total = category_lines
                 .iter()
                 .filter(|l| {
                     let mut splitted = l.date_raw().split('/');
                     let _ = splitted.next().unwrap().parse::<u32>().unwrap();
                     let month = splitted.next().unwrap().parse::<u32>().unwrap();
                     let year = splitted.next().unwrap().parse::<i32>().unwrap();
-                    current_date.year() == year
-                        && current_date.month() == month
-                        && l.categorie() != "Transaction exclue"
+                    current_date.year() == year && current_date.month() == month
                 })
                 .map(|l| l.credit().unwrap_or(0.0) + l.debit().unwrap_or(0.0))
                 .sum::<f32>();
 
-            months.push(current_date);
+            months_sums.push(category_month_total);
+
             current_date = current_date
                 .checked_add_months(chrono::Months::new(1))
                 .unwrap();
         }
-        let active_months = months.clone();
-        let min_active_month = active_months.iter().min().unwrap();
-        let max_active_month = active_months.iter().max().unwrap();
 
-        let lines = lines
-            .into_iter()
-            .filter(|l| {
-                let mut splitted = l.date_raw().split('/');
-                let _ = splitted.next().unwrap().parse::<u32>().unwrap();
-                let month = splitted.next().unwrap().parse::<u32>().unwrap();
-                let year = splitted.next().unwrap().parse::<i32>().unwrap();
-                let date = chrono::NaiveDate::from_ymd_opt(year, month, 1).unwrap();
-                &date >= min_active_month && &date <= max_active_month
-            })
-            .collect::<Vec<Line>>();
+        categories_totals.push((category.clone(), months_sums, sum, average))
+    }
+    let categories_totals = categories_totals
+        .into_iter()
+        .sorted_by_key(|(_, _, total, _)| (total * 100.) as i32)
+        .collect::<Vec<(String, Vec<f32>, f32, f32)>>();
 
-        let categories: Vec<String> = lines
+    let mut sub_categories_total = vec![];
+    for (category, sub_category) in &sub_categories {
+        let sub_category_lines = lines
             .iter()
-            .map(|l| l.categorie().to_string())
-            .collect::<Vec<String>>()
-            .into_iter()
-            .unique()
-            .collect();
-
-        let mut sub_categories: Vec<(String, String)> = vec![];
-        for category in &categories {
-            for sub_category in lines
-                .iter()
-                .filter(|l| l.categorie() == category)
-                .map(|l| l.sous_categorie().to_string())
-                .collect::<Vec<String>>()
-                .into_iter()
-                .unique()
-                .collect::<Vec<String>>()
-            {
-                sub_categories.push((category.clone(), sub_category))
-            }
-        }
+            .filter(|l| l.categorie() == category && l.sous_categorie() == sub_category)
+            .collect::<Vec<&Line>>();
+        let sum = sub_category_lines
+            .iter()
+            .map(|l| l.credit().unwrap_or_default() + l.debit().unwrap_or_default())
+            .sum();
+        let average = sum / active_months_count as f32;
 
-        let active_months_count = months_between(&min_active_month, &max_active_month) + 1;
-        let mut categories_totals = vec![];
-        for category in &categories {
-            let category_lines = lines
-                .iter()
-                .filter(|l| l.categorie() == category)
-                .collect::<Vec<&Line>>();
-            let sum = category_lines
+        let mut months_sums = vec![];
+        let mut current_date =
+            chrono::NaiveDate::from_ymd_opt(lower_date.year(), lower_date.month(), 1).unwrap();
+        while current_date <= higher_date {
+            let sub_category_month_total = sub_category_lines
                 .iter()
-                .map(|l| l.credit().unwrap_or_default() + l.debit().unwrap_or_default())
-                .sum();
-            let average = sum / active_months_count as f32;
-
-            let mut months_sums = vec![];
-            let mut current_date =
-                chrono::NaiveDate::from_ymd_opt(lower_date.year(), lower_date.month(), 1).unwrap();
-            while current_date <= higher_date {
-                let category_month_total = category_lines
-                    .iter()
-                    .filter(|l| {
-                        let mut splitted = l.date_raw().split('/');
-                        let _ = splitted.next().unwrap().parse::<u32>().unwrap();
-                        let month = splitted.next().unwrap().parse::<u32>().unwrap();
-                        let year = splitted.next().unwrap().parse::<i32>().unwrap();
-                        current_date.year() == year && current_date.month() == month
-                    })
-                    .map(|l| l.credit().unwrap_or(0.0) + l.debit().unwrap_or(0.0))
-                    .sum::<f32>();
-
-                months_sums.push(category_month_total);
+                .filter(|l| {
+                    let mut splitted = l.date_raw().split('/');
+                    let _ = splitted.next().unwrap().parse::<u32>().unwrap();
+                    let month = splitted.next().unwrap().parse::<u32>().unwrap();
+                    let year = splitted.next().unwrap().parse::<i32>().unwrap();
+                    current_date.year() == year && current_date.month() == month
+                })
+                .map(|l| l.credit().unwrap_or(0.0) + l.debit().unwrap_or(0.0))
+                .sum::<f32>();
 
-                current_date = current_date
-                    .checked_add_months(chrono::Months::new(1))
-                    .unwrap();
-            }
+            months_sums.push(sub_category_month_total);
 
-            categories_totals.push((category.clone(), months_sums, sum, average))
+            current_date = current_date
+                .checked_add_months(chrono::Months::new(1))
+                .unwrap();
         }
-        let categories_totals = categories_totals
-            .into_iter()
-            .sorted_by_key(|(_, _, total, _)| (total * 100.) as i32)
-            .collect::<Vec<(String, Vec<f32>, f32, f32)>>();
-
-        let mut sub_categories_total = vec![];
-        for (category, sub_category) in &sub_categories {
-            let sub_category_lines = lines
-                .iter()
-                .filter(|l| l.categorie() == category && l.sous_categorie() == sub_category)
-                .collect::<Vec<&Line>>();
-            let sum = sub_category_lines
-                .iter()
-                .map(|l| l.credit().unwrap_or_default() + l.debit().unwrap_or_default())
-                .sum();
-            let average = sum / active_months_count as f32;
-
-            let mut months_sums = vec![];
-            let mut current_date =
-                chrono::NaiveDate::from_ymd_opt(lower_date.year(), lower_date.month(), 1).unwrap();
-            while current_date <= higher_date {
-                let sub_category_month_total = sub_category_lines
-                    .iter()
-                    .filter(|l| {
-                        let mut splitted = l.date_raw().split('/');
-                        let _ = splitted.next().unwrap().parse::<u32>().unwrap();
-                        let month = splitted.next().unwrap().parse::<u32>().unwrap();
-                        let year = splitted.next().unwrap().parse::<i32>().unwrap();
-                        current_date.year() == year && current_date.month() == month
-                    })
-                    .map(|l| l.credit().unwrap_or(0.0) + l.debit().unwrap_or(0.0))
-                    .sum::<f32>();
 
-                months_sums.push(sub_category_month_total);
+        sub_categories_total.push((
+            category.clone(),
+            sub_category.clone(),
+            months_sums,
+            sum,
+            average,
+        ))
+    }
+    let sub_categories_total = sub_categories_total
+        .into_iter()
+        .sorted_by_key(|(_, _, _, total, _)| (total * 100.) as i32)
+        .collect::<Vec<(String, String, Vec<f32>, f32, f32)>>();
 
-                current_date = current_date
-                    .checked_add_months(chrono::Months::new(1))
-                    .unwrap();
-            }
+    let mut months_count = 1;
 
-            sub_categories_total.push((
-                category.clone(),
-                sub_category.clone(),
-                months_sums,
-                sum,
-                average,
-            ))
-        }
-        let sub_categories_total = sub_categories_total
-            .into_iter()
-            .sorted_by_key(|(_, _, _, total, _)| (total * 100.) as i32)
-            .collect::<Vec<(String, String, Vec<f32>, f32, f32)>>();
-
-        let mut months_count = 1;
+    let mut categories_histogram = vec![];
+    for category in &categories {
+        let category_lines = lines
+            .iter()
+            .filter(|l| l.categorie() == category)
+            .collect::<Vec<&Line>>();
 
-        let mut categories_histogram = vec![];
-        for category in &categories {
-            let category_lines = lines
+        let mut index = 0;
+        let mut values = vec![];
+        let mut current_date =
+            chrono::NaiveDate::from_ymd_opt(lower_date.year(), lower_date.month(), 1).unwrap();
+        while current_date <= higher_date {
+            let category_month_total = category_lines
                 .iter()
-                .filter(|l| l.categorie() == category)
-                .collect::<Vec<&Line>>();
-
-            let mut index = 0;
-            let mut values = vec![];
-            let mut current_date =
-                chrono::NaiveDate::from_ymd_opt(lower_date.year(), lower_date.month(), 1).unwrap();
-            while current_date <= higher_date {
-                let category_month_total = category_lines
-                    .iter()
-                    .filter(|l| {
-                        let mut splitted = l.date_raw().split('/');
-                        let _ = splitted.next().unwrap().parse::<u32>().unwrap();
-                        let month = splitted.next().unwrap().parse::<u32>().unwrap();
-                        let year = splitted.next().unwrap().parse::<i32>().unwrap();
-                        current_date.year() == year && current_date.month() == month
-                    })
-                    .map(|l| l.credit().unwrap_or(0.0) + l.debit().unwrap_or(0.0))
-                    .sum::<f32>();
-
-                values.push([(index + 1) as f64, category_month_total as f64]);
-
-                current_date = current_date
-                    .checked_add_months(chrono::Months::new(1))
-                    .unwrap();
-                index += 1;
-            }
+                .filter(|l| {
+                    let mut splitted = l.date_raw().split('/');
+                    let _ = splitted.next().unwrap().parse::<u32>().unwrap();
+                    let month = splitted.next().unwrap().parse::<u32>().unwrap();
+                    let year = splitted.next().unwrap().parse::<i32>().unwrap();
+                    current_date.year() == year && current_date.month() == month
+                })
+                .map(|l| l.credit().unwrap_or(0.0) + l.debit().unwrap_or(0.0))
+                .sum::<f32>();
 
-            let positive = values
-                .iter()
-                .map(|[_, v]| v)
-                .sum::<f64>()
-                .is_sign_positive();
+            values.push([index as f64, category_month_total as f64]);
 
-            categories_histogram.push((category.clone(), positive, values));
-            months_count = index;
+            current_date = current_date
+                .checked_add_months(chrono::Months::new(1))
+                .unwrap();
+            index += 1;
         }
 
-        let recurring_months = (((months_count as isize) - 1).max(1)) as usize;
+        let positive = values
+            .iter()
+            .map(|[_, v]| v)
+            .sum::<f64>()
+            .is_sign_positive();
 
-        let mut sous_categories_histogram = vec![];
-        for (category, sub_category) in &sub_categories {
-            let sub_category_lines = lines
-                .iter()
-                .filter(|l| l.categorie() == category && l.sous_categorie() == sub_category)
-                .collect::<Vec<&Line>>();
-
-            let mut index = 0;
-            let mut values = vec![];
-            let mut current_date =
-                chrono::NaiveDate::from_ymd_opt(lower_date.year(), lower_date.month(), 1).unwrap();
-            while current_date <= higher_date {
-                let sub_category_month_total = sub_category_lines
-                    .iter()
-                    .filter(|l| {
-                        let mut splitted = l.date_raw().split('/');
-                        let _ = splitted.next().unwrap().parse::<u32>().unwrap();
-                        let month = splitted.next().unwrap().parse::<u32>().unwrap();
-                        let year = splitted.next().unwrap().parse::<i32>().unwrap();
-                        current_date.year() == year && current_date.month() == month
-                    })
-                    .map(|l| l.credit().unwrap_or(0.0) + l.debit().unwrap_or(0.0))
-                    .sum::<f32>();
+        categories_histogram.push((category.clone(), positive, values));
+        months_count = index;
+    }
 
-                values.push([(index + 1) as f64, sub_category_month_total as f64]);
+    let recurring_months = (((months_count as isize) - 1).max(1)) as usize;
 
-                current_date = current_date
-                    .checked_add_months(chrono::Months::new(1))
-                    .unwrap();
-                index += 1;
-            }
-
-            let positive = values
-                .iter()
-                .map(|[_, v]| v)
-                .sum::<f64>()
-                .is_sign_positive();
-
-            sous_categories_histogram.push((
-                category.clone(),
-                sub_category.clone(),
-                positive,
-                values,
-            ));
-        }
+    let mut sous_categories_histogram = vec![];
+    for (category, sub_category) in &sub_categories {
+        let sub_category_lines = lines
+            .iter()
+            .filter(|l| l.categorie() == category && l.sous_categorie() == sub_category)
+            .collect::<Vec<&Line>>();
 
+        let mut index = 0;
+        let mut values = vec![];
         let mut current_date =
             chrono::NaiveDate::from_ymd_opt(lower_date.year(), lower_date.month(), 1).unwrap();
         while current_date <= higher_date {
-            let month_total = lines
+            let sub_category_month_total = sub_category_lines
                 .iter()
                 .filter(|l| {
                     let mut splitted = l.date_raw().split('/');
                     let _ = splitted.next().unwrap().parse::<u32>().unwrap();
                     let month = splitted.next().unwrap().parse::<u32>().unwrap();
                     let year = splitted.next().unwrap().parse::<i32>().unwrap();
-                    current_date.year() == year
-                        && current_date.month() == month
-                        && l.categorie() != "Transaction exclue"
+                    current_date.year() == year && current_date.month() == month
                 })
                 .map(|l| l.credit().unwrap_or(0.0) + l.debit().unwrap_or(0.0))
                 .sum::<f32>();
 
-            months_sums.push((current_date.clone(), month_total));
+            values.push([index as f64, sub_category_month_total as f64]);
+
             current_date = current_date
                 .checked_add_months(chrono::Months::new(1))
                 .unwrap();
+            index += 1;
         }
 
-        let recurring_approx = 0.0;
-        let mut lines = Lines::new(
-            name,
-            lower_date,
-            higher_date,
-            lines,
-            vec![],
-            categories,
-            sub_categories,
-            categories_totals,
-            sub_categories_total,
-            categories_histogram,
-            sous_categories_histogram,
-            months_count,
-            months,
-            active_months,
-            recurring_months,
-            recurring_approx,
-            true,
-            months_sums,
-        );
+        let positive = values
+            .iter()
+            .map(|[_, v]| v)
+            .sum::<f64>()
+            .is_sign_positive();
 
-        let recurring = extract_recuring(&lines);
-        lines.recurring = recurring;
+        sous_categories_histogram.push((category.clone(), sub_category.clone(), positive, values));
+    }
 
-        Ok(lines)
+    let mut months_flows = vec![];
+    let mut current_date =
+        chrono::NaiveDate::from_ymd_opt(lower_date.year(), lower_date.month(), 1).unwrap();
+    while current_date <= higher_date {
+        let month_total = lines
+            .iter()
+            .filter(|l| {
+                let mut splitted = l.date_raw().split('/');
+                let _ = splitted.next().unwrap().parse::<u32>().unwrap();
+                let month = splitted.next().unwrap().parse::<u32>().unwrap();
+                let year = splitted.next().unwrap().parse::<i32>().unwrap();
+                current_date.year() == year
+                    && current_date.month() == month
+                    && l.categorie() != EXCLUDED_CATEGORY
+            })
+            .map(|l| l.credit().unwrap_or(0.0) + l.debit().unwrap_or(0.0))
+            .sum::<f32>();
+
+        months_sums.push((current_date, month_total));
+
+        let (income, expenses) = lines
+            .iter()
+            .filter(|l| {
+                l.date().year() == current_date.year()
+                    && l.date().month() == current_date.month()
+                    && l.categorie() != EXCLUDED_CATEGORY
+            })
+            .map(|l| l.amount())
+            .fold((0.0, 0.0), |(income, expenses), amount| {
+                if amount > 0.0 {
+                    (income + amount, expenses)
+                } else {
+                    (income, expenses + amount)
+                }
+            });
+        months_flows.push(MonthFlows::new(current_date, income, expenses));
+
+        current_date = current_date
+            .checked_add_months(chrono::Months::new(1))
+            .unwrap();
     }
+
+    let period = Period {
+        lower_date,
+        higher_date,
+        lines,
+        categories,
+        sub_categories,
+        categories_totals,
+        sub_categories_total,
+        categories_histogram,
+        sous_categories_histogram,
+        months_count,
+        all_months: months,
+        active_months,
+        months_sums,
+        months_flows,
+        ..Default::default()
+    };
+    let mut lines = Lines::new(
+        name,
+        all_lines,
+        range,
+        period,
+        Recurring::default(),
+        Parameters::new(recurring_months),
+    );
+    lines.period.daily_spending = daily_spending(&lines);
+    lines.period.month_anomalies = month_anomalies(&lines);
+    lines.period.line_anomalies = line_anomalies(&lines);
+    lines.period.merchants = merchants(&lines);
+
+    lines
+}
+
+/// Same imported lines over another date range, keeping current settings, with
+/// recurring series detected according to user decisions
+pub fn with_range(lines: &Lines, range: DateRange, decisions: &[RecurringDecision]) -> Lines {
+    build_lines_with(
+        lines.name().to_string(),
+        lines.all_lines().clone(),
+        range,
+        lines.parameters.clone(),
+        decisions,
+    )
 }
 
-pub fn extract_recuring(lines: &Lines) -> Vec<Line> {
+/// Imported lines over the date range with the given parameters, and recurring
+/// series detected according to user decisions
+pub fn build_lines_with(
+    name: String,
+    all_lines: Arc<Vec<Line>>,
+    range: DateRange,
+    parameters: Parameters,
+    decisions: &[RecurringDecision],
+) -> Lines {
+    let mut lines = build_lines(name, all_lines, range);
+    lines.parameters = parameters;
+    lines.set_average_mode(lines.parameters.average_mode);
+    update_recurring(&mut lines, decisions);
+    lines
+}
+
+/// Compute recurring series, their price changes, missed payments, the forecast and
+/// the balance timeline according to current settings and user decisions
+pub fn update_recurring(lines: &mut Lines, decisions: &[RecurringDecision]) {
+    lines.recurring.series = extract_recuring(lines, decisions);
+    lines.recurring.price_changes = extract_price_changes(lines, &lines.recurring.series);
+    lines.recurring.missed_payments =
+        extract_missed_payments(&lines.recurring.series, lines.higher_date());
+    update_forecast(lines);
+}
+
+/// Compute the forecast and the balance timeline (which goes as far as the forecast)
+/// from the current recurring series
+pub fn update_forecast(lines: &mut Lines) {
+    lines.recurring.forecast = extract_forecast(lines);
+    lines.recurring.balance_timeline = extract_balance_timeline(lines);
+}
+
+pub fn extract_recuring(lines: &Lines, decisions: &[RecurringDecision]) -> Vec<RecurringSeries> {
+    let lines_ = lines.lines();
+    let amounts = lines_
+        .iter()
+        .map(|l| (l.debit().unwrap_or(0.0), l.credit().unwrap_or(0.0)))
+        .collect::<Vec<(f32, f32)>>();
+    let groups = PayeeGroups::new(
+        lines_.iter().map(|l| l.payee()),
+        amounts.iter().map(|(debit, credit)| debit + credit),
+        lines.parameters.recurring_similarity,
+    );
+    let similar_amounts = |reference: usize, line: usize| {
+        approx_eq_pct_ref(
+            amounts[reference].0,
+            amounts[line].0,
+            lines.parameters.recurring_approx,
+        ) && approx_eq_pct_ref(
+            amounts[reference].1,
+            amounts[line].1,
+            lines.parameters.recurring_approx,
+        )
+    };
+
     let mut recurring = vec![];
+    // Series reference lines, by payee
+    let mut references: Vec<Vec<usize>> = vec![vec![]; groups.payees_count()];
+    // Decisions matching each payee, compared once by payee
+    let mut payee_decisions: Vec<Option<Vec<&RecurringDecision>>> =
+        vec![None; groups.payees_count()];
 
-    for line in lines.lines() {
-        let mut found_counter = 0;
-        let mut current_date =
-            chrono::NaiveDate::from_ymd_opt(lines.lower_date.year(), lines.lower_date.month(), 1)
-                .unwrap();
-        while current_date <= lines.higher_date {
-            let month_lines = lines
-                .lines()
-                .iter()
-                .filter(|l| {
-                    let mut splitted = l.date_raw().split('/');
-                    let _ = splitted.next().unwrap().parse::<u32>().unwrap();
-                    let month = splitted.next().unwrap().parse::<u32>().unwrap();
-                    let year = splitted.next().unwrap().parse::<i32>().unwrap();
-                    current_date.year() == year && current_date.month() == month
-                })
-                .collect::<Vec<&Line>>();
+    for (i, line) in lines_.iter().enumerate() {
+        let payee = groups.payee(i);
+
+        let decision = payee_decisions[payee]
+            .get_or_insert_with(|| {
+                decisions
+                    .iter()
+                    .filter(|d| {
+                        d.matches_payee(line.payee(), lines.parameters.recurring_similarity)
+                    })
+                    .collect()
+            })
+            .iter()
+            .find(|d| d.matches_amount(line.amount(), lines.parameters.recurring_approx))
+            .map(|d| d.decision());
+        if decision == Some(Decision::Dismissed) {
+            continue;
+        }
+
+        // A line similar to an already found series is part of it
+        if groups
+            .similar_payees(payee)
+            .iter()
+            .flat_map(|p| &references[*p])
+            .any(|j| similar_amounts(*j, i))
+        {
+            continue;
+        }
+
+        let range = similar_amounts_range(
+            amounts[i].0,
+            amounts[i].1,
+            lines.parameters.recurring_approx,
+        );
+        let similar_lines = groups
+            .lines(payee, range)
+            .filter(|j| similar_amounts(*j, i))
+            .sorted()
+            .collect::<Vec<usize>>();
+        // Not enough occurrences for any periodicity
+        if similar_lines.len() < lines.recurring_months().min(2)
+            && decision != Some(Decision::Pinned)
+        {
+            continue;
+        }
+
+        let series_dates = similar_lines
+            .iter()
+            .map(|j| lines_[*j].date())
+            .sorted()
+            .dedup()
+            .collect::<Vec<chrono::NaiveDate>>();
+        let found_counter = series_dates
+            .iter()
+            .map(|date| (date.year(), date.month()))
+            .dedup()
+            .count();
+        let periodicity = median_gap(&series_dates).and_then(Periodicity::from_days);
+
+        // Lines found enough months are recurring whatever their interval, other ones
+        // (like quarterly or yearly charges) need a known periodicity
+        let periodicity = if found_counter >= lines.recurring_months() {
+            periodicity.unwrap_or(Periodicity::Monthly)
+        } else if let Some(periodicity) = periodicity
+            && periodicity
+                .min_occurrences()
+                .is_some_and(|min| series_dates.len() >= min)
+        {
+            periodicity
+        } else if decision == Some(Decision::Pinned) {
+            periodicity.unwrap_or(Periodicity::Monthly)
+        } else {
+            continue;
+        };
 
-            if !month_lines
+        let series_payee = similar_lines
+            .iter()
+            .map(|j| lines_[*j].payee())
+            .min_by_key(|p| p.len())
+            .unwrap()
+            .to_string();
+        let occurrences = similar_lines
+            .into_iter()
+            .sorted_by_key(|j| lines_[*j].date())
+            .map(|j| lines_[j].clone())
+            .collect::<Vec<Line>>();
+        let next_date = periodicity.next(*series_dates.last().unwrap());
+        recurring.push(RecurringSeries::new(
+            series_payee,
+            line.clone(),
+            occurrences,
+            periodicity,
+            next_date,
+        ));
+        references[payee].push(i);
+    }
+
+    recurring
+}
+
+/// Amount changes between consecutive occurrences of recurring series, including
+/// when a series with a new price follows another one of the same payee, or when a
+/// payee lines settle at a new amount (which may split them in series too short to
+/// be recurring).
+pub fn extract_price_changes(lines: &Lines, recurring: &[RecurringSeries]) -> Vec<PriceChange> {
+    let threshold = lines.parameters.price_change_threshold;
+    let changed = |old: &Line, new: &Line| {
+        old.amount() != 0.0
+            && ((new.amount() - old.amount()) / old.amount()).abs() * 100.0 > threshold
+    };
+    let mut price_changes = vec![];
+
+    for series in recurring {
+        for (old, new) in series.occurrences().iter().tuple_windows() {
+            if changed(old, new) {
+                price_changes.push(PriceChange::new(
+                    series.payee().to_string(),
+                    series.periodicity(),
+                    new.date(),
+                    old.amount(),
+                    new.amount(),
+                ));
+            }
+        }
+
+        // Previous series of the same payee ending about one period before this one
+        let previous = recurring
+            .iter()
+            .filter(|s| {
+                s.payee() == series.payee()
+                    && s.periodicity() == series.periodicity()
+                    && s.last_date() < series.first_date()
+                    && series.periodicity().next(s.last_date())
+                        >= series.first_date() - chrono::Days::new(7)
+            })
+            .max_by_key(|s| s.last_date());
+        if let Some(previous) = previous {
+            let old = previous.occurrences().last().unwrap();
+            let new = series.occurrences().first().unwrap();
+            if changed(old, new) {
+                price_changes.push(PriceChange::new(
+                    series.payee().to_string(),
+                    series.periodicity(),
+                    new.date(),
+                    old.amount(),
+                    new.amount(),
+                ));
+            }
+        }
+    }
+
+    for (payee, payee_lines) in lines.lines().iter().into_group_map_by(|line| line.payee()) {
+        // Runs of consecutive lines with a similar amount
+        let mut runs: Vec<Vec<&Line>> = vec![];
+        for line in payee_lines.into_iter().sorted_by_key(|line| line.date()) {
+            match runs.last_mut() {
+                Some(run)
+                    if approx_eq_pct_ref(
+                        run[0].amount(),
+                        line.amount(),
+                        lines.parameters.recurring_approx,
+                    ) =>
+                {
+                    run.push(line)
+                }
+                _ => runs.push(vec![line]),
+            }
+        }
+
+        for (old, new) in runs.iter().tuple_windows() {
+            if old.len() < 2 || new.len() < 2 {
+                continue;
+            }
+            // Both runs together must be recurring, one period apart
+            let dates = old
                 .iter()
-                .filter(|l| {
-                    l.libelle_simplifie() == line.libelle_simplifie()
-                        && approx_eq_pct_ref(
-                            l.debit().unwrap_or(0.0),
-                            line.debit().unwrap_or(0.0),
-                            lines.recurring_approx,
-                        )
-                        && approx_eq_pct_ref(
-                            l.credit().unwrap_or(0.0),
-                            line.credit().unwrap_or(0.0),
-                            lines.recurring_approx,
-                        )
-                })
-                .collect::<Vec<&&Line>>()
-                .is_empty()
+                .chain(new)
+                .map(|line| line.date())
+                .dedup()
+                .collect::<Vec<chrono::NaiveDate>>();
+            let Some(periodicity) = median_gap(&dates).and_then(Periodicity::from_days) else {
+                continue;
+            };
+            let months = dates
+                .iter()
+                .map(|date| (date.year(), date.month()))
+                .dedup()
+                .count();
+            let recurring = months >= lines.recurring_months()
+                || periodicity
+                    .min_occurrences()
+                    .is_some_and(|min| dates.len() >= min);
+            let (old, new) = (old.last().unwrap(), new.first().unwrap());
+            if recurring
+                && periodicity.next(old.date()) >= new.date() - chrono::Days::new(7)
+                && changed(old, new)
             {
-                found_counter += 1;
+                price_changes.push(PriceChange::new(
+                    payee.to_string(),
+                    periodicity,
+                    new.date(),
+                    old.amount(),
+                    new.amount(),
+                ));
             }
-            current_date = current_date
-                .checked_add_months(chrono::Months::new(1))
-                .unwrap();
+        }
+    }
+
+    price_changes
+        .into_iter()
+        .unique_by(|c| (c.payee().to_string(), c.date()))
+        .sorted_by_key(|c| c.date())
+        .rev()
+        .collect()
+}
+
+/// Expected occurrences of recurring series, in the month of the latest imported
+/// line, which are missing. Occurrences expected in the last days before the latest
+/// line are not reported yet as they can be registered late.
+pub fn extract_missed_payments(
+    recurring: &[RecurringSeries],
+    higher_date: chrono::NaiveDate,
+) -> Vec<MissedPayment> {
+    let period_start = higher_date.with_day(1).unwrap();
+    let deadline = higher_date - chrono::Days::new(MISSED_PAYMENT_DELAY_DAYS);
+    let mut missed_payments = vec![];
+
+    for series in recurring {
+        // Series already missing before this period are considered as ended
+        if series.next_date() < period_start {
+            continue;
+        }
+
+        let expected_amount = series.occurrences().last().unwrap().amount();
+        let mut expected_date = series.next_date();
+
+        while expected_date <= deadline {
+            // A series of the same payee (like after a price change) may have taken over
+            let taken_over = recurring.iter().any(|s| {
+                s.payee() == series.payee()
+                    && s.occurrences().iter().any(|l| {
+                        (l.date() - expected_date).num_days().abs()
+                            <= MISSED_PAYMENT_DELAY_DAYS as i64
+                    })
+            });
+            if !taken_over {
+                missed_payments.push(MissedPayment::new(
+                    series.payee().to_string(),
+                    series.periodicity(),
+                    expected_date,
+                    expected_amount,
+                ));
+            }
+            expected_date = series.periodicity().next(expected_date);
+        }
+    }
+
+    missed_payments
+        .into_iter()
+        .sorted_by_key(|m| m.expected_date())
+        .collect()
+}
+
+/// Income and expenses of the coming months: occurrences of ongoing recurring series
+/// plus the average of remaining (variable) amounts by category.
+pub fn extract_forecast(lines: &Lines) -> Vec<MonthForecast> {
+    let period_start = lines.higher_date().with_day(1).unwrap();
+    let ongoing = lines
+        .recurring()
+        .iter()
+        .filter(|s| s.next_date() >= period_start)
+        .collect::<Vec<&RecurringSeries>>();
+
+    let mut variable_income = 0.0;
+    let mut variable_expenses = 0.0;
+    let mut variance = 0.0;
+    for (category, months, _, average) in lines.categories_totals() {
+        if category == EXCLUDED_CATEGORY {
+            continue;
         }
 
-        if found_counter >= lines.recurring_months {
-            recurring.push(line.clone())
+        let recurring_cost = ongoing
+            .iter()
+            .filter(|s| s.line().categorie() == category)
+            .map(|s| s.occurrences().last().unwrap().amount() * s.periodicity().per_month())
+            .sum::<f32>();
+        let variable = average - recurring_cost;
+        if variable > 0.0 {
+            variable_income += variable;
+        } else {
+            variable_expenses += variable;
+        }
+        variance += months.iter().map(|v| (v - average).powi(2)).sum::<f32>() / months.len() as f32;
+    }
+
+    (1..=lines.parameters.forecast_months as u32)
+        .map(|i| {
+            let month = period_start + chrono::Months::new(i);
+            let month_end = month + chrono::Months::new(1);
+            let mut income = variable_income;
+            let mut expenses = variable_expenses;
+
+            for series in &ongoing {
+                let amount = series.occurrences().last().unwrap().amount();
+                let mut date = series.next_date();
+                while date < month_end {
+                    if date >= month {
+                        if amount > 0.0 {
+                            income += amount;
+                        } else {
+                            expenses += amount;
+                        }
+                    }
+                    date = series.periodicity().next(date);
+                }
+            }
+
+            // Variable spending deviations add up over the months ahead
+            MonthForecast::new(month, income, expenses, (variance * i as f32).sqrt())
+        })
+        .collect()
+}
+
+/// Running balance of imported lines, computed back from the balance given by the
+/// user, followed by expected recurring movements until the end of the forecast.
+/// Missed payments are expected the day after the latest imported line.
+pub fn extract_balance_timeline(lines: &Lines) -> Vec<BalanceEvent> {
+    let Some(current_balance) = lines.parameters.balance else {
+        return vec![];
+    };
+    // Given balance is the one after the latest imported line, whatever the range
+    let balance = current_balance
+        - lines
+            .all_lines()
+            .iter()
+            .filter(|l| l.date() > lines.higher_date())
+            .map(|l| l.amount())
+            .sum::<f32>();
+
+    let mut timeline = vec![];
+    let imported = lines
+        .lines()
+        .iter()
+        .sorted_by_key(|l| l.date())
+        .collect::<Vec<&Line>>();
+    let mut running = balance - imported.iter().map(|l| l.amount()).sum::<f32>();
+    for line in imported {
+        running += line.amount();
+        timeline.push(BalanceEvent::new(
+            line.date(),
+            line.payee().to_string(),
+            line.amount(),
+            running,
+            false,
+        ));
+    }
+
+    // Expected movements come after the latest imported line, whatever the range
+    let latest = lines
+        .all_lines()
+        .iter()
+        .map(|l| l.date())
+        .max()
+        .unwrap_or(lines.higher_date());
+    let period_start = lines.higher_date().with_day(1).unwrap();
+    let horizon = latest.with_day(1).unwrap()
+        + chrono::Months::new(lines.parameters.forecast_months as u32 + 1);
+    let tomorrow = latest + chrono::Days::new(1);
+    // Missed payments are only expected when the range reaches the latest line
+    let mut expected = if lines.higher_date() == latest {
+        lines
+            .missed_payments()
+            .iter()
+            .map(|m| (tomorrow, m.payee(), m.expected_amount()))
+            .collect::<Vec<_>>()
+    } else {
+        vec![]
+    };
+    for series in lines
+        .recurring()
+        .iter()
+        .filter(|s| s.next_date() >= period_start)
+    {
+        let amount = series.occurrences().last().unwrap().amount();
+        let mut date = series.next_date();
+        while date < horizon {
+            if date > latest {
+                expected.push((date, series.payee(), amount));
+            }
+            date = series.periodicity().next(date);
         }
     }
 
-    let mut recurring_: Vec<Line> = vec![];
-    for line in &recurring {
-        let already = recurring_
-            .iter()
-            .find(|l| {
-                l.libelle_simplifie() == line.libelle_simplifie()
-                    && approx_eq_pct_ref(
-                        l.debit().unwrap_or(0.0),
-                        line.debit().unwrap_or(0.0),
-                        lines.recurring_approx,
-                    )
-                    && approx_eq_pct_ref(
-                        l.credit().unwrap_or(0.0),
-                        line.credit().unwrap_or(0.0),
-                        lines.recurring_approx,
-                    )
+    running = current_balance;
+    for (date, payee, amount) in expected.into_iter().sorted_by_key(|(d, _, _)| *d) {
+        running += amount;
+        timeline.push(BalanceEvent::new(
+            date,
+            payee.to_string(),
+            amount,
+            running,
+            true,
+        ));
+    }
+
+    timeline
+}
+
+/// Amounts (debit + credit) range containing all lines with amounts similar to the
+/// given ones (see [`approx_eq_pct_ref`]), if bounded.
+fn similar_amounts_range(debit: f32, credit: f32, pct: f32) -> Option<RangeInclusive<f32>> {
+    if pct >= 1.0 {
+        return None;
+    }
+
+    let bounds = |value: f32| {
+        let (a, b) = (value / (1.0 + pct), value / (1.0 - pct));
+        (a.min(b), a.max(b))
+    };
+    let (debit_min, debit_max) = bounds(debit);
+    let (credit_min, credit_max) = bounds(credit);
+    // Absorb rounding differences with the exact comparison
+    let margin = 0.01 + (debit.abs() + credit.abs()) * 1e-4;
+
+    Some((debit_min + credit_min - margin)..=(debit_max + credit_max + margin))
+}
+
+/// Lines grouped by normalized payee, with similar payees computed once.
+struct PayeeGroups {
+    /// Payee index of each line
+    line_payees: Vec<usize>,
+    /// Similar payees indexes (including itself) of each payee
+    similar_payees: Vec<Vec<usize>>,
+    /// Lines indexes of each payee, sorted by amount
+    payee_lines: Vec<Vec<(f32, usize)>>,
+}
+
+impl PayeeGroups {
+    fn new<'a>(
+        payees: impl Iterator<Item = &'a str>,
+        amounts: impl Iterator<Item = f32>,
+        similarity: f32,
+    ) -> Self {
+        let mut indexes: HashMap<&str, usize> = HashMap::new();
+        let mut distinct: Vec<&str> = vec![];
+        let line_payees = payees
+            .map(|payee| {
+                *indexes.entry(payee).or_insert_with(|| {
+                    distinct.push(payee);
+                    distinct.len() - 1
+                })
+            })
+            .collect::<Vec<usize>>();
+        let words = distinct
+            .iter()
+            .map(|payee| payee.split(' ').collect::<Vec<&str>>())
+            .collect::<Vec<Vec<&str>>>();
+
+        let mut by_word: HashMap<&str, Vec<usize>> = HashMap::new();
+        for (index, words) in words.iter().enumerate() {
+            for word in words.iter().unique() {
+                by_word.entry(word).or_default().push(index);
+            }
+        }
+
+        // Only payees sharing at least one word may be similar (unless any payee is
+        // accepted), similarity being the share of common words
+        let mut seen = vec![false; distinct.len()];
+        let similar_payees = words
+            .iter()
+            .map(|words_| {
+                if similarity <= 0.0 {
+                    return (0..distinct.len()).collect();
+                }
+
+                let others = words_
+                    .iter()
+                    .flat_map(|word| &by_word[word])
+                    .copied()
+                    .filter(|other| !std::mem::replace(&mut seen[*other], true))
+                    .collect::<Vec<usize>>();
+                others.iter().for_each(|other| seen[*other] = false);
+
+                others
+                    .into_iter()
+                    .filter(|other| words_similarity(&words[*other], words_) >= similarity)
+                    .sorted()
+                    .collect()
             })
-            .is_some();
+            .collect::<Vec<Vec<usize>>>();
 
-        if !already {
-            recurring_.push(line.clone());
+        let mut payee_lines: Vec<Vec<(f32, usize)>> = vec![vec![]; distinct.len()];
+        for (line, (payee, amount)) in line_payees.iter().zip(amounts).enumerate() {
+            payee_lines[*payee].push((amount, line));
         }
+        payee_lines
+            .iter_mut()
+            .for_each(|lines| lines.sort_by(|(a, _), (b, _)| a.total_cmp(b)));
+
+        Self {
+            line_payees,
+            similar_payees,
+            payee_lines,
+        }
+    }
+
+    fn payees_count(&self) -> usize {
+        self.similar_payees.len()
+    }
+
+    fn payee(&self, line: usize) -> usize {
+        self.line_payees[line]
+    }
+
+    fn similar_payees(&self, payee: usize) -> &[usize] {
+        &self.similar_payees[payee]
     }
 
-    recurring_
+    /// Lines indexes (unordered) of the payee and its similar payees, with amount in
+    /// the given range (if any)
+    fn lines(
+        &self,
+        payee: usize,
+        range: Option<RangeInclusive<f32>>,
+    ) -> impl Iterator<Item = usize> {
+        self.similar_payees[payee].iter().flat_map(move |payee| {
+            let lines = &self.payee_lines[*payee];
+            let (start, end) = match &range {
+                Some(range) => (
+                    lines.partition_point(|(amount, _)| amount.total_cmp(range.start()).is_lt()),
+                    lines.partition_point(|(amount, _)| amount.total_cmp(range.end()).is_le()),
+                ),
+                None => (0, lines.len()),
+            };
+            lines[start..end].iter().map(|(_, line)| *line)
+        })
+    }
 }
 
 pub fn approx_eq_pct_ref(reference: f32, actual: f32, pct: f32) -> bool {
@@ -469,6 +1010,10 @@ fn months_between(start: &chrono::NaiveDate, end: &chrono::NaiveDate) -> i32 {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{
+        recurring::{normalize_payee, payee_similarity},
+        stats::{AverageMode, Statistic},
+    };
 
     const RAW: &str = "Date de comptabilisation;Libelle simplifie;Libelle operation;Reference;Informations complementaires;Type operation;Categorie;Sous categorie;Debit;Credit;Date operation;Date de valeur;Pointage operation
 22/03/2025;AREA;CB AREA NFC FACT 200325;;;Carte bancaire;Transports;Peage et Stationnement;-2,4;;20/03/2025;24/03/2025;0
@@ -479,6 +1024,225 @@ mod tests {
 21/03/2025;INTERMARCHE;CB INTERMARCHE FACT 190325;;;Carte bancaire;Alimentation;Hyper/supermarche;-76,18;;19/03/2025;21/03/2025;0
 ";
 
+    fn raw(lines: &[(&str, &str, &str)]) -> String {
+        let mut raw = RAW.lines().next().unwrap().to_string();
+        for (date, libelle, debit) in lines {
+            raw.push_str(&format!(
+                "\n{date};{libelle};PRLV SEPA {libelle};;;Prelevement;Divers;Divers;{debit};;{date};{date};0"
+            ));
+        }
+        raw
+    }
+
+    fn periodicities_raw() -> String {
+        let mut rows = vec![
+            ("10/01/2024", "ASSURANCE AUTO", "-480"),
+            ("08/01/2025", "ASSURANCE AUTO", "-480"),
+            ("15/01/2024", "EAU", "-45,5"),
+            ("15/04/2024", "EAU", "-45,5"),
+            ("16/07/2024", "EAU", "-45,5"),
+            ("15/10/2024", "EAU", "-45,5"),
+            ("02/03/2024", "SUPERMARCHE", "-20"),
+            ("02/06/2024", "SUPERMARCHE", "-20"),
+        ];
+        let rent_dates = (1..=12)
+            .map(|month| format!("05/{month:02}/2024"))
+            .chain(["05/01/2025".to_string()])
+            .collect::<Vec<String>>();
+        rows.extend(rent_dates.iter().map(|d| (d.as_str(), "LOYER", "-650")));
+        raw(&rows)
+    }
+
+    /// Labels made of references or repeated words
+    fn odd_labels_raw() -> String {
+        let dates = (1..=12)
+            .map(|month| format!("07/{month:02}/2024"))
+            .collect::<Vec<String>>();
+        let rows = dates
+            .iter()
+            .enumerate()
+            .flat_map(|(i, date)| {
+                [
+                    (
+                        date.as_str(),
+                        ["CB MAG12 FACT 0701", "CB MAG12 FACT 0702"][i % 2],
+                        "-15",
+                    ),
+                    (date.as_str(), "VIR 200325", "-100"),
+                    (
+                        date.as_str(),
+                        ["TAXE TAXE FONCIERE", "TAXE FONCIERE"][i % 2],
+                        "-80",
+                    ),
+                ]
+            })
+            .collect::<Vec<_>>();
+        raw(&rows)
+    }
+
+    /// Lines of 250 payees over 40 months, one line a month each
+    fn many_lines_raw() -> String {
+        let dates = (0..40)
+            .map(|month| format!("12/{:02}/{}", month % 12 + 1, 2020 + month / 12))
+            .collect::<Vec<String>>();
+        let payees = (0..250)
+            .map(|i| {
+                format!(
+                    "MARCHAND {}{}",
+                    (b'A' + (i / 26) as u8) as char,
+                    (b'A' + (i % 26) as u8) as char
+                )
+            })
+            .collect::<Vec<String>>();
+        let amounts = (0..40 * 250)
+            .map(|i| format!("-{}", (i * 37) % 500 + 1))
+            .collect::<Vec<String>>();
+        let rows = dates
+            .iter()
+            .enumerate()
+            .flat_map(|(month, date)| {
+                payees
+                    .iter()
+                    .enumerate()
+                    .map(move |(payee, name)| (month, date, payee, name))
+            })
+            .map(|(month, date, payee, name)| {
+                // A third of the payees have a fixed amount
+                let amount = if payee % 3 == 0 {
+                    "-25"
+                } else {
+                    amounts[month * 250 + payee].as_str()
+                };
+                (date.as_str(), name.as_str(), amount)
+            })
+            .collect::<Vec<_>>();
+        raw(&rows)
+    }
+
+    fn fuzzy_payees_raw() -> String {
+        let dates = (1..=12)
+            .map(|month| format!("03/{month:02}/2024"))
+            .collect::<Vec<String>>();
+        let rows = dates
+            .iter()
+            .enumerate()
+            .map(|(i, date)| {
+                let libelle = if i % 2 == 0 {
+                    "PRLV SEPA EDF"
+                } else {
+                    "PRLV SEPA EDF CLIENTS"
+                };
+                (date.as_str(), libelle, "-62,3")
+            })
+            .collect::<Vec<_>>();
+        raw(&rows)
+    }
+
+    /// Previous (quadratic) implementation of `extract_recuring`, frozen to be used as
+    /// a reference
+    fn extract_recuring_reference(lines: &Lines) -> Vec<RecurringSeries> {
+        let payees = lines
+            .lines()
+            .iter()
+            .map(|l| normalize_payee(l.libelle_simplifie()))
+            .collect::<Vec<String>>();
+        let similar = |i: usize, j: usize| {
+            is_similar(
+                &lines.lines()[i],
+                &payees[i],
+                &lines.lines()[j],
+                &payees[j],
+                lines.parameters.recurring_approx,
+                lines.parameters.recurring_similarity,
+            )
+        };
+        let mut recurring = vec![];
+
+        for (i, line) in lines.lines().iter().enumerate() {
+            let similar_lines = (0..lines.lines().len())
+                .filter(|j| similar(*j, i))
+                .collect::<Vec<usize>>();
+            let found_counter = similar_lines
+                .iter()
+                .map(|j| {
+                    let date = lines.lines()[*j].date();
+                    (date.year(), date.month())
+                })
+                .unique()
+                .count();
+            let dates = similar_lines
+                .iter()
+                .map(|j| lines.lines()[*j].date())
+                .sorted()
+                .dedup()
+                .collect::<Vec<chrono::NaiveDate>>();
+            let periodicity = median_gap(&dates).and_then(Periodicity::from_days);
+
+            // Lines found enough months are recurring whatever their interval, other ones
+            // (like quarterly or yearly charges) need a known periodicity
+            let periodicity = if found_counter >= lines.recurring_months() {
+                periodicity.unwrap_or(Periodicity::Monthly)
+            } else if let Some(periodicity) = periodicity
+                && periodicity
+                    .min_occurrences()
+                    .is_some_and(|min| dates.len() >= min)
+            {
+                periodicity
+            } else {
+                continue;
+            };
+
+            let payee = similar_lines
+                .iter()
+                .map(|j| &payees[*j])
+                .min_by_key(|p| p.len())
+                .unwrap()
+                .clone();
+            let occurrences = similar_lines
+                .into_iter()
+                .map(|j| lines.lines()[j].clone())
+                .sorted_by_key(|l| l.date())
+                .collect::<Vec<Line>>();
+            let next_date = periodicity.next(*dates.last().unwrap());
+            recurring.push((
+                i,
+                RecurringSeries::new(payee, line.clone(), occurrences, periodicity, next_date),
+            ));
+        }
+
+        let mut recurring_: Vec<(usize, RecurringSeries)> = vec![];
+        for (i, series) in recurring {
+            let already = recurring_.iter().any(|(j, _)| similar(*j, i));
+
+            if !already {
+                recurring_.push((i, series));
+            }
+        }
+
+        recurring_.into_iter().map(|(_, series)| series).collect()
+    }
+
+    fn is_similar(
+        reference: &Line,
+        reference_payee: &str,
+        line: &Line,
+        payee: &str,
+        approx: f32,
+        similarity: f32,
+    ) -> bool {
+        payee_similarity(reference_payee, payee) >= similarity
+            && approx_eq_pct_ref(
+                reference.debit().unwrap_or(0.0),
+                line.debit().unwrap_or(0.0),
+                approx,
+            )
+            && approx_eq_pct_ref(
+                reference.credit().unwrap_or(0.0),
+                line.credit().unwrap_or(0.0),
+                approx,
+            )
+    }
+
     #[test]
     fn test_months_between() {
         assert_eq!(
@@ -562,4 +1326,535 @@ mod tests {
             ]
         )
     }
+
+    #[test]
+    fn extract_recurring_periodicities() {
+        // Given
+        let raw = periodicities_raw();
+
+        // When
+        let lines = raw.into_lines("test".to_string()).unwrap();
+
+        // Then
+        let recurring = lines
+            .recurring()
+            .iter()
+            .map(|s| {
+                (
+                    s.line().libelle_simplifie(),
+                    s.periodicity(),
+                    s.occurrences().len(),
+                    s.next_date(),
+                )
+            })
+            .sorted_by_key(|(libelle, _, _, _)| *libelle)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            recurring,
+            vec![
+                (
+                    "ASSURANCE AUTO",
+                    Periodicity::Yearly,
+                    2,
+                    chrono::NaiveDate::from_ymd_opt(2026, 1, 8).unwrap()
+                ),
+                (
+                    "EAU",
+                    Periodicity::Quarterly,
+                    4,
+                    chrono::NaiveDate::from_ymd_opt(2025, 1, 15).unwrap()
+                ),
+                (
+                    "LOYER",
+                    Periodicity::Monthly,
+                    13,
+                    chrono::NaiveDate::from_ymd_opt(2025, 2, 5).unwrap()
+                ),
+            ]
+        )
+    }
+
+    #[test]
+    fn extract_recurring_payee_with_digits() {
+        // Given
+        let rows = (1..=12)
+            .map(|month| {
+                (
+                    format!("01/{month:02}/2024"),
+                    format!("CB O2SWITCH FACT 01{month:02}24"),
+                )
+            })
+            .collect::<Vec<_>>();
+        let rows = rows
+            .iter()
+            .map(|(date, libelle)| (date.as_str(), libelle.as_str(), "-7,2"))
+            .collect::<Vec<_>>();
+
+        // When
+        let lines = raw(&rows).into_lines("test".to_string()).unwrap();
+
+        // Then
+        assert_eq!(lines.recurring().len(), 1);
+        assert_eq!(lines.recurring()[0].payee(), "O2SWITCH");
+        assert_eq!(lines.recurring()[0].occurrences().len(), 12);
+        assert_eq!(lines.merchants().len(), 1);
+        assert_eq!(lines.merchants()[0].count(), 12);
+    }
+
+    #[test]
+    fn extract_recurring_fuzzy_payees() {
+        // Given
+        let raw = fuzzy_payees_raw();
+
+        // When
+        let lines = raw.into_lines("test".to_string()).unwrap();
+
+        // Then
+        assert_eq!(lines.recurring().len(), 1);
+        assert_eq!(lines.recurring()[0].payee(), "EDF");
+        assert_eq!(lines.recurring()[0].occurrences().len(), 12);
+    }
+
+    #[test]
+    fn extract_recurring_with_decisions() {
+        // Given
+        let mut lines = periodicities_raw().into_lines("test".to_string()).unwrap();
+        let decisions = [
+            RecurringDecision::new("SUPERMARCHE".to_string(), -20.0, Decision::Pinned),
+            RecurringDecision::new("EAU".to_string(), -45.5, Decision::Dismissed),
+        ];
+
+        // When
+        update_recurring(&mut lines, &decisions);
+
+        // Then
+        let recurring = lines
+            .recurring()
+            .iter()
+            .map(|s| (s.payee(), s.occurrences().len()))
+            .sorted()
+            .collect::<Vec<_>>();
+        assert_eq!(
+            recurring,
+            vec![("ASSURANCE AUTO", 2), ("LOYER", 13), ("SUPERMARCHE", 2)]
+        );
+    }
+
+    #[test]
+    fn extract_missed_payments_in_latest_month() {
+        // Given
+        let rent_dates = (1..=11)
+            .map(|month| format!("05/{month:02}/2024"))
+            .collect::<Vec<String>>();
+        let mut rows = rent_dates
+            .iter()
+            .map(|d| (d.as_str(), "LOYER", "-650"))
+            .collect::<Vec<_>>();
+        rows.extend([
+            ("15/01/2024", "EAU", "-45,5"),
+            ("15/04/2024", "EAU", "-45,5"),
+            ("15/07/2024", "EAU", "-45,5"),
+            ("20/12/2024", "SUPERMARCHE", "-20"),
+        ]);
+        let mut lines = raw(&rows).into_lines("test".to_string()).unwrap();
+
+        // When
+        lines.parameters.recurring_months = 6;
+        update_recurring(&mut lines, &[]);
+
+        // Then
+        assert_eq!(
+            lines.missed_payments(),
+            vec![MissedPayment::new(
+                "LOYER".to_string(),
+                Periodicity::Monthly,
+                chrono::NaiveDate::from_ymd_opt(2024, 12, 5).unwrap(),
+                -650.0,
+            )]
+        );
+    }
+
+    #[test]
+    fn extract_forecast_from_recurring_and_variable() {
+        // Given
+        let rent_dates = (1..=12)
+            .map(|month| format!("05/{month:02}/2024"))
+            .collect::<Vec<String>>();
+        let shopping_dates = (1..=12)
+            .map(|month| format!("10/{month:02}/2024"))
+            .collect::<Vec<String>>();
+        let rows = rent_dates
+            .iter()
+            .map(|d| (d.as_str(), "LOYER", "-650"))
+            .chain(shopping_dates.iter().enumerate().map(|(i, d)| {
+                let amount = if i % 2 == 0 { "-100" } else { "-300" };
+                (d.as_str(), "SUPERMARCHE", amount)
+            }))
+            .collect::<Vec<_>>();
+        let mut lines = raw(&rows).into_lines("test".to_string()).unwrap();
+
+        // When
+        lines.parameters.forecast_months = 2;
+        update_recurring(&mut lines, &[]);
+
+        // Then
+        assert_eq!(
+            lines.forecast(),
+            vec![
+                MonthForecast::new(
+                    chrono::NaiveDate::from_ymd_opt(2025, 1, 1).unwrap(),
+                    0.0,
+                    -850.0,
+                    100.0,
+                ),
+                MonthForecast::new(
+                    chrono::NaiveDate::from_ymd_opt(2025, 2, 1).unwrap(),
+                    0.0,
+                    -850.0,
+                    100.0 * 2.0f32.sqrt(),
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn extract_balance_timeline_overdraft() {
+        // Given
+        let rent_dates = (1..=12)
+            .map(|month| format!("05/{month:02}/2024"))
+            .collect::<Vec<String>>();
+        let rows = rent_dates
+            .iter()
+            .map(|d| (d.as_str(), "LOYER", "-650"))
+            .collect::<Vec<_>>();
+        let mut lines = raw(&rows).into_lines("test".to_string()).unwrap();
+
+        // When
+        lines.parameters.balance = Some(1000.0);
+        lines.parameters.forecast_months = 2;
+        update_recurring(&mut lines, &[]);
+
+        // Then
+        let timeline = lines.balance_timeline();
+        assert_eq!(timeline.len(), 14);
+        assert_eq!(timeline[0].balance(), 8150.0);
+        assert_eq!(timeline[11].balance(), 1000.0);
+        assert_eq!(
+            lines.overdraft_risks().cloned().collect::<Vec<_>>(),
+            vec![BalanceEvent::new(
+                chrono::NaiveDate::from_ymd_opt(2025, 2, 5).unwrap(),
+                "LOYER".to_string(),
+                -650.0,
+                -300.0,
+                true,
+            )]
+        );
+    }
+
+    #[test]
+    fn range_round_trip_keeps_recurring_months() {
+        // Given
+        let rows = [
+            ("05/01/2024", "LOYER", "-650"),
+            ("08/01/2024", "CINEMA", "-12"),
+            ("05/02/2024", "LOYER", "-650"),
+            ("05/03/2024", "LOYER", "-650"),
+            ("09/03/2024", "BOULANGERIE", "-4"),
+        ];
+        let lines = raw(&rows).into_lines("test".to_string()).unwrap();
+
+        // When
+        let this_month = with_range(&lines, DateRange::ThisMonth, &[]);
+        let all = with_range(&this_month, DateRange::All, &[]);
+
+        // Then
+        assert_eq!(this_month.recurring_months(), 1);
+        assert_eq!(
+            all.parameters.recurring_months,
+            lines.parameters.recurring_months
+        );
+        assert_eq!(all.recurring().len(), 1);
+        assert_eq!(all.recurring()[0].payee(), "LOYER");
+    }
+
+    #[test]
+    fn extract_balance_timeline_past_range() {
+        // Given
+        let rent_dates = (1..=15)
+            .map(|month| format!("05/{:02}/{}", (month - 1) % 12 + 1, 2024 + (month - 1) / 12))
+            .collect::<Vec<String>>();
+        let rows = rent_dates
+            .iter()
+            .map(|d| (d.as_str(), "LOYER", "-650"))
+            .collect::<Vec<_>>();
+        let mut lines = raw(&rows).into_lines("test".to_string()).unwrap();
+        lines.parameters.balance = Some(1000.0);
+        lines.parameters.forecast_months = 2;
+
+        // When
+        let last_year = with_range(&lines, DateRange::LastYear, &[]);
+
+        // Then
+        let expected = last_year
+            .balance_timeline()
+            .iter()
+            .filter(|e| e.expected())
+            .map(|e| (e.date(), e.balance()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            expected,
+            vec![
+                (chrono::NaiveDate::from_ymd_opt(2025, 4, 5).unwrap(), 350.0),
+                (chrono::NaiveDate::from_ymd_opt(2025, 5, 5).unwrap(), -300.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn lines_with_range() {
+        // Given
+        let rows = [
+            ("05/11/2023", "EAU", "-40"),
+            ("05/01/2024", "LOYER", "-650"),
+            ("05/02/2024", "LOYER", "-650"),
+            ("10/03/2024", "EAU", "-45"),
+        ];
+        let mut lines = raw(&rows).into_lines("test".to_string()).unwrap();
+        lines.parameters.balance = Some(1000.0);
+
+        // When
+        let this_month = with_range(&lines, DateRange::ThisMonth, &[]);
+        let last_year = with_range(&lines, DateRange::LastYear, &[]);
+        let custom = with_range(
+            &lines,
+            DateRange::Custom(
+                chrono::NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+                chrono::NaiveDate::from_ymd_opt(2024, 2, 29).unwrap(),
+            ),
+            &[],
+        );
+
+        // Then
+        assert_eq!(this_month.lines().len(), 1);
+        assert_eq!(
+            this_month.lower_date(),
+            chrono::NaiveDate::from_ymd_opt(2024, 3, 1).unwrap()
+        );
+        assert_eq!(last_year.lines().len(), 1);
+        assert_eq!(last_year.months_count(), 12);
+        assert_eq!(custom.lines().len(), 2);
+        assert_eq!(custom.all_lines().len(), 4);
+        assert_eq!(
+            custom.months_sums().iter().map(|(_, v)| v).sum::<f32>(),
+            -1300.0
+        );
+        let imported = custom
+            .balance_timeline()
+            .iter()
+            .filter(|e| !e.expected())
+            .collect::<Vec<_>>();
+        assert_eq!(imported.last().unwrap().balance(), 1045.0);
+    }
+
+    #[test]
+    fn sort_totals_by_statistic() {
+        // Given
+        let rows = [
+            ("05/01/2024", "MEUBLE", "-900"),
+            ("05/01/2024", "SUPER", "-200"),
+            ("05/02/2024", "SUPER", "-200"),
+            ("05/03/2024", "SUPER", "-200"),
+        ];
+        let raw = raw(&rows).replace(
+            "PRLV SEPA MEUBLE;;;Prelevement;Divers;Divers",
+            "PRLV SEPA MEUBLE;;;Prelevement;Maison;Meubles",
+        );
+        let mut lines = raw.into_lines("test".to_string()).unwrap();
+        let order = |lines: &Lines| {
+            lines
+                .categories_totals()
+                .iter()
+                .map(|(category, _, _, _)| category.clone())
+                .collect::<Vec<String>>()
+        };
+
+        // When/Then
+        assert_eq!(order(&lines), vec!["Maison", "Divers"]);
+        lines.sort_totals(Statistic::Median);
+        assert_eq!(order(&lines), vec!["Divers", "Maison"]);
+        lines.sort_totals(Statistic::ActiveMonths);
+        assert_eq!(order(&lines), vec!["Divers", "Maison"]);
+        lines.sort_totals(Statistic::Min);
+        assert_eq!(order(&lines), vec!["Maison", "Divers"]);
+    }
+
+    #[test]
+    fn averages_without_partial_months() {
+        // Given
+        let rows = [
+            ("22/01/2024", "SUPER", "-40"),
+            ("10/02/2024", "SUPER", "-120"),
+            ("10/03/2024", "SUPER", "-50"),
+        ];
+        let mut lines = raw(&rows).into_lines("test".to_string()).unwrap();
+        assert_eq!(lines.categories_totals()[0].3, -70.0);
+
+        // When
+        lines.set_average_mode(AverageMode::CompleteMonths);
+
+        // Then
+        assert_eq!(lines.categories_totals()[0].3, -120.0);
+        assert_eq!(lines.sub_categories_total()[0].4, -120.0);
+    }
+
+    #[test]
+    fn extract_recurring_same_as_reference() {
+        let amounts = ["-61,2", "-62,3", "-64", "-58,9", "1500", "1523,4"];
+        let dates = (1..=12)
+            .map(|month| format!("10/{month:02}/2024"))
+            .collect::<Vec<String>>();
+        let varied_rows = dates
+            .iter()
+            .enumerate()
+            .map(|(i, date)| (date.as_str(), "EDF", amounts[i % amounts.len()]))
+            .collect::<Vec<_>>();
+
+        for raw_ in [
+            RAW.to_string(),
+            periodicities_raw(),
+            fuzzy_payees_raw(),
+            odd_labels_raw(),
+            raw(&varied_rows),
+        ] {
+            let mut lines = raw_.into_lines("test".to_string()).unwrap();
+
+            for recurring_months in 1..=lines.months_count() {
+                for recurring_approx in [0.0, 0.02, 0.1, 0.5, 1.0, 2.0] {
+                    for recurring_similarity in [0.0, 0.5, 0.8, 1.0] {
+                        lines.parameters.recurring_months = recurring_months;
+                        lines.parameters.recurring_approx = recurring_approx;
+                        lines.parameters.recurring_similarity = recurring_similarity;
+
+                        assert_eq!(
+                            extract_recuring(&lines, &[]),
+                            extract_recuring_reference(&lines)
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn extract_recurring_many_lines() {
+        // Given
+        let mut lines = many_lines_raw().into_lines("test".to_string()).unwrap();
+        lines.parameters.recurring_approx = 0.1;
+
+        // When
+        let recurring = extract_recuring(&lines, &[]);
+
+        // Then
+        assert_eq!(lines.lines().len(), 10_000);
+        assert!(recurring.len() >= 250 / 3);
+    }
+
+    /// Timing check, run with `cargo test --release -- --ignored`
+    #[test]
+    #[ignore]
+    fn extract_recurring_many_lines_duration() {
+        // Given
+        let mut lines = many_lines_raw().into_lines("test".to_string()).unwrap();
+        lines.parameters.recurring_approx = 0.1;
+
+        // When
+        let start = std::time::Instant::now();
+        let _ = extract_recuring(&lines, &[]);
+        let elapsed = start.elapsed();
+
+        // Then
+        assert!(elapsed < std::time::Duration::from_secs(1), "{elapsed:?}");
+    }
+
+    #[test]
+    fn extract_price_changes_with_default_parameters() {
+        // Given
+        let dates = (1..=12)
+            .map(|month| format!("07/{month:02}/2024"))
+            .collect::<Vec<String>>();
+        let rows = dates
+            .iter()
+            .enumerate()
+            .flat_map(|(i, date)| {
+                let amount = if i < 6 { "-13,49" } else { "-15,99" };
+                // Variable amounts are not price changes
+                [
+                    (date.as_str(), "NETFLIX", amount.to_string()),
+                    (date.as_str(), "CARREFOUR", format!("-{}", 45 + i * 3)),
+                ]
+            })
+            .collect::<Vec<_>>();
+        let rows = rows
+            .iter()
+            .map(|(date, libelle, debit)| (*date, *libelle, debit.as_str()))
+            .collect::<Vec<_>>();
+
+        // When
+        let lines = raw(&rows).into_lines("test".to_string()).unwrap();
+
+        // Then
+        assert!(lines.recurring().is_empty());
+        assert_eq!(
+            lines.price_changes(),
+            vec![PriceChange::new(
+                "NETFLIX".to_string(),
+                Periodicity::Monthly,
+                chrono::NaiveDate::from_ymd_opt(2024, 7, 7).unwrap(),
+                -13.49,
+                -15.99,
+            )]
+        );
+    }
+
+    #[test]
+    fn extract_price_changes_between_series() {
+        // Given
+        let dates = (1..=12)
+            .map(|month| format!("07/{month:02}/2024"))
+            .collect::<Vec<String>>();
+        let rows = dates
+            .iter()
+            .enumerate()
+            .map(|(i, date)| {
+                let amount = if i < 6 { "-13,49" } else { "-15,99" };
+                (date.as_str(), "NETFLIX", amount)
+            })
+            .collect::<Vec<_>>();
+        let mut lines = raw(&rows).into_lines("test".to_string()).unwrap();
+
+        // When
+        lines.parameters.recurring_months = 6;
+        update_recurring(&mut lines, &[]);
+
+        // Then
+        assert_eq!(
+            lines.price_changes(),
+            vec![PriceChange::new(
+                "NETFLIX".to_string(),
+                Periodicity::Monthly,
+                chrono::NaiveDate::from_ymd_opt(2024, 7, 7).unwrap(),
+                -13.49,
+                -15.99,
+            )]
+        );
+        assert_eq!(lines.price_changes()[0].yearly_impact(), -30.0);
+
+        // When
+        lines.parameters.recurring_approx = 0.5;
+        update_recurring(&mut lines, &[]);
+
+        // Then
+        assert_eq!(lines.recurring().len(), 1);
+        assert_eq!(lines.price_changes().len(), 1);
+    }
 }
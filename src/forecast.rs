@@ -0,0 +1,73 @@
+use derive_more::Constructor;
+
+/// Expected amounts of a coming month.
+#[derive(Debug, Clone, PartialEq, Constructor)]
+pub struct MonthForecast {
+    month: chrono::NaiveDate,
+    income: f32,
+    expenses: f32,
+    /// Standard deviation of the month balance, from variable spending, growing with
+    /// the square root of the months ahead
+    uncertainty: f32,
+}
+
+impl MonthForecast {
+    pub fn month(&self) -> chrono::NaiveDate {
+        self.month
+    }
+
+    pub fn income(&self) -> f32 {
+        self.income
+    }
+
+    pub fn expenses(&self) -> f32 {
+        self.expenses
+    }
+
+    /// Month balance (income + expenses), comparable to [`crate::line::Lines::months_sums`]
+    pub fn balance(&self) -> f32 {
+        self.income + self.expenses
+    }
+
+    pub fn uncertainty(&self) -> f32 {
+        self.uncertainty
+    }
+}
+
+/// Account movement with the balance after it, either imported or expected from
+/// a recurring series.
+#[derive(Debug, Clone, PartialEq, Constructor)]
+pub struct BalanceEvent {
+    date: chrono::NaiveDate,
+    payee: String,
+    amount: f32,
+    balance: f32,
+    expected: bool,
+}
+
+impl BalanceEvent {
+    pub fn date(&self) -> chrono::NaiveDate {
+        self.date
+    }
+
+    pub fn payee(&self) -> &str {
+        &self.payee
+    }
+
+    pub fn amount(&self) -> f32 {
+        self.amount
+    }
+
+    pub fn balance(&self) -> f32 {
+        self.balance
+    }
+
+    pub fn expected(&self) -> bool {
+        self.expected
+    }
+
+    /// Expected movement bringing the balance below the given threshold
+    pub fn is_overdraft_risk(&self, threshold: f32) -> bool {
+        self.expected && self.balance < threshold
+    }
+}
@@ -1,7 +1,20 @@
-use derive_more::Constructor;
-use std::hash::{Hash, Hasher};
+use chrono::Datelike;
+use derive_more::{Constructor, Display};
+use std::{
+    hash::{Hash, Hasher},
+    sync::Arc,
+};
 
-#[derive(Debug, Constructor, PartialEq, Clone)]
+use crate::{
+    anomaly::{LineAnomaly, MonthAnomaly},
+    forecast::{BalanceEvent, MonthForecast},
+    merchant::Merchant,
+    recurring::{MissedPayment, PriceChange, RecurringSeries, normalize_payee},
+    savings::MonthFlows,
+    stats::{AverageMode, MonthStats, Statistic},
+};
+
+#[derive(Debug, PartialEq, Clone)]
 pub struct Line {
     date_raw: String,
     libelle_simplifie: String,
@@ -11,9 +24,47 @@ pub struct Line {
     sous_categorie: String,
     debit: Option<f32>,
     credit: Option<f32>,
+    // Computed once from raw values
+    date: chrono::NaiveDate,
+    payee: String,
 }
 
 impl Line {
+    // One argument per imported column
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        date_raw: String,
+        libelle_simplifie: String,
+        libelle_operation: String,
+        reference: String,
+        categorie: String,
+        sous_categorie: String,
+        debit: Option<f32>,
+        credit: Option<f32>,
+    ) -> Self {
+        let date = {
+            let mut splitted = date_raw.split('/');
+            let day = splitted.next().unwrap().parse::<u32>().unwrap();
+            let month = splitted.next().unwrap().parse::<u32>().unwrap();
+            let year = splitted.next().unwrap().parse::<i32>().unwrap();
+            chrono::NaiveDate::from_ymd_opt(year, month, day).unwrap()
+        };
+        let payee = normalize_payee(&libelle_simplifie);
+
+        Self {
+            date_raw,
+            libelle_simplifie,
+            libelle_operation,
+            reference,
+            categorie,
+            sous_categorie,
+            debit,
+            credit,
+            date,
+            payee,
+        }
+    }
+
     pub fn libelle_simplifie(&self) -> &str {
         &self.libelle_simplifie
     }
@@ -22,10 +73,6 @@ impl Line {
         &self.libelle_operation
     }
 
-    pub fn reference(&self) -> &str {
-        &self.reference
-    }
-
     pub fn categorie(&self) -> &str {
         &self.categorie
     }
@@ -45,6 +92,20 @@ impl Line {
     pub fn date_raw(&self) -> &str {
         &self.date_raw
     }
+
+    /// Signed amount (credit or debit)
+    pub fn amount(&self) -> f32 {
+        self.credit.unwrap_or(0.0) + self.debit.unwrap_or(0.0)
+    }
+
+    pub fn date(&self) -> chrono::NaiveDate {
+        self.date
+    }
+
+    /// Normalized payee name (see [`normalize_payee`])
+    pub fn payee(&self) -> &str {
+        &self.payee
+    }
 }
 
 impl Hash for Line {
@@ -62,26 +123,131 @@ impl Hash for Line {
 
 impl Eq for Line {}
 
-#[derive(Debug, Constructor)]
-pub struct Lines {
-    name: String,
+/// Period of imported lines to consider. Relative periods are based on the latest
+/// imported line, as files are usually imported some time after.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Display)]
+pub enum DateRange {
+    #[default]
+    #[display("Tout")]
+    All,
+    #[display("Ce mois")]
+    ThisMonth,
+    #[display("3 derniers mois")]
+    LastThreeMonths,
+    #[display("Cette année")]
+    ThisYear,
+    #[display("Année dernière")]
+    LastYear,
+    #[display("Personnalisée")]
+    Custom(chrono::NaiveDate, chrono::NaiveDate),
+}
+
+impl DateRange {
+    /// Range first and last dates, given the first and latest imported lines dates
+    pub fn bounds(
+        &self,
+        lower_date: chrono::NaiveDate,
+        higher_date: chrono::NaiveDate,
+    ) -> (chrono::NaiveDate, chrono::NaiveDate) {
+        let month_start = higher_date.with_day(1).unwrap();
+        let year_start = month_start.with_month(1).unwrap();
+        match self {
+            DateRange::All => (lower_date, higher_date),
+            DateRange::ThisMonth => (month_start, higher_date),
+            DateRange::LastThreeMonths => (month_start - chrono::Months::new(2), higher_date),
+            DateRange::ThisYear => (year_start, higher_date),
+            DateRange::LastYear => (
+                year_start - chrono::Months::new(12),
+                year_start - chrono::Days::new(1),
+            ),
+            DateRange::Custom(start, end) => (*start, (*end).max(*start)),
+        }
+    }
+}
+
+/// Lines within the date range and their aggregates, computed once per range
+#[derive(Debug, Clone, Default)]
+pub struct Period {
     pub lower_date: chrono::NaiveDate,
     pub higher_date: chrono::NaiveDate,
-    lines: Vec<Line>,
-    pub recurring: Vec<Line>,
-    categories: Vec<String>,
-    sub_categories: Vec<(String, String)>,
-    categories_totals: Vec<(String, Vec<f32>, f32, f32)>,
-    sub_categories_total: Vec<(String, String, Vec<f32>, f32, f32)>,
-    categories_histogram: Vec<(String, bool, Vec<[f64; 2]>)>,
-    sous_categories_histogram: Vec<(String, String, bool, Vec<[f64; 2]>)>,
+    pub lines: Vec<Line>,
+    pub categories: Vec<String>,
+    pub sub_categories: Vec<(String, String)>,
+    pub categories_totals: Vec<(String, Vec<f32>, f32, f32)>,
+    pub sub_categories_total: Vec<(String, String, Vec<f32>, f32, f32)>,
+    pub categories_histogram: Vec<(String, bool, Vec<[f64; 2]>)>,
+    pub sous_categories_histogram: Vec<(String, String, bool, Vec<[f64; 2]>)>,
     pub months_count: usize,
     pub all_months: Vec<chrono::NaiveDate>,
     pub active_months: Vec<chrono::NaiveDate>,
+    pub months_sums: Vec<(chrono::NaiveDate, f32)>,
+    pub months_flows: Vec<MonthFlows>,
+    pub month_anomalies: Vec<MonthAnomaly>,
+    pub line_anomalies: Vec<LineAnomaly>,
+    pub merchants: Vec<Merchant>,
+    /// Spending of each day, see [`crate::calendar::daily_spending`]
+    pub daily_spending: Vec<(chrono::NaiveDate, f32)>,
+}
+
+/// Recurring series and what derives from them, depending on the parameters
+#[derive(Debug, Clone, Default)]
+pub struct Recurring {
+    pub series: Vec<RecurringSeries>,
+    pub price_changes: Vec<PriceChange>,
+    pub missed_payments: Vec<MissedPayment>,
+    pub forecast: Vec<MonthForecast>,
+    pub balance_timeline: Vec<BalanceEvent>,
+}
+
+/// User parameters of the computations, kept when the date range changes
+#[derive(Debug, Clone)]
+pub struct Parameters {
     pub recurring_months: usize,
     pub recurring_approx: f32,
+    pub recurring_similarity: f32,
+    /// Minimum amount variation (in percent) to report a price change
+    pub price_change_threshold: f32,
+    /// Number of coming months to forecast
+    pub forecast_months: usize,
+    /// Account balance after the latest imported line (whatever the date range),
+    /// given by the user
+    pub balance: Option<f32>,
+    /// Balance under which expected movements are reported as overdraft risks
+    pub overdraft_threshold: f32,
     pub categories_histogram_display_expenses_only: bool,
-    pub months_sums: Vec<(chrono::NaiveDate, f32)>,
+    /// Statistic driving categories and sub-categories totals order
+    pub totals_sort: Statistic,
+    /// Months counted in categories and sub-categories averages
+    pub average_mode: AverageMode,
+}
+
+impl Parameters {
+    /// Default parameters, detecting series recurring over the given months count
+    pub fn new(recurring_months: usize) -> Self {
+        Self {
+            recurring_months,
+            recurring_approx: 0.0,
+            recurring_similarity: 0.8,
+            price_change_threshold: 2.0,
+            forecast_months: 6,
+            balance: None,
+            overdraft_threshold: 0.0,
+            categories_histogram_display_expenses_only: true,
+            totals_sort: Statistic::Total,
+            average_mode: AverageMode::AllMonths,
+        }
+    }
+}
+
+#[derive(Debug, Constructor, Clone)]
+pub struct Lines {
+    name: String,
+    /// All imported lines, whatever the date range, shared with undo history
+    all_lines: Arc<Vec<Line>>,
+    range: DateRange,
+    pub period: Period,
+    pub recurring: Recurring,
+    pub parameters: Parameters,
 }
 
 impl Lines {
@@ -89,51 +255,151 @@ impl Lines {
         &self.name
     }
 
+    pub fn lower_date(&self) -> chrono::NaiveDate {
+        self.period.lower_date
+    }
+
+    pub fn higher_date(&self) -> chrono::NaiveDate {
+        self.period.higher_date
+    }
+
     pub fn lines(&self) -> &[Line] {
-        &self.lines
+        &self.period.lines
+    }
+
+    pub fn all_lines(&self) -> &Arc<Vec<Line>> {
+        &self.all_lines
+    }
+
+    /// Months count required for a series to be recurring, at most the period months
+    /// count
+    pub fn recurring_months(&self) -> usize {
+        self.parameters.recurring_months.min(self.months_count())
+    }
+
+    pub fn range(&self) -> DateRange {
+        self.range
+    }
+
+    pub fn recurring(&self) -> &[RecurringSeries] {
+        &self.recurring.series
+    }
+
+    pub fn price_changes(&self) -> &[PriceChange] {
+        &self.recurring.price_changes
+    }
+
+    pub fn missed_payments(&self) -> &[MissedPayment] {
+        &self.recurring.missed_payments
+    }
+
+    pub fn forecast(&self) -> &[MonthForecast] {
+        &self.recurring.forecast
     }
 
-    pub fn recurring(&self) -> &[Line] {
-        &self.recurring
+    pub fn balance_timeline(&self) -> &[BalanceEvent] {
+        &self.recurring.balance_timeline
+    }
+
+    /// Expected movements bringing the balance below the overdraft threshold
+    pub fn overdraft_risks(&self) -> impl Iterator<Item = &BalanceEvent> {
+        self.recurring
+            .balance_timeline
+            .iter()
+            .filter(|e| e.is_overdraft_risk(self.parameters.overdraft_threshold))
     }
 
     pub fn categories(&self) -> &[String] {
-        &self.categories
+        &self.period.categories
     }
 
     pub fn sub_categories(&self) -> &[(String, String)] {
-        &self.sub_categories
+        &self.period.sub_categories
     }
 
     pub fn categories_totals(&self) -> &[(String, Vec<f32>, f32, f32)] {
-        &self.categories_totals
+        &self.period.categories_totals
     }
 
     pub fn sub_categories_total(&self) -> &[(String, String, Vec<f32>, f32, f32)] {
-        &self.sub_categories_total
+        &self.period.sub_categories_total
     }
 
     pub fn categories_histogram(&self) -> &[(String, bool, Vec<[f64; 2]>)] {
-        &self.categories_histogram
+        &self.period.categories_histogram
     }
 
     pub fn sous_categories_histogram(&self) -> &[(String, String, bool, Vec<[f64; 2]>)] {
-        &self.sous_categories_histogram
+        &self.period.sous_categories_histogram
     }
 
     pub fn months_count(&self) -> usize {
-        self.months_count
+        self.period.months_count
     }
 
     pub fn all_months(&self) -> &Vec<chrono::NaiveDate> {
-        &self.all_months
+        &self.period.all_months
     }
 
     pub fn active_months(&self) -> &[chrono::NaiveDate] {
-        &self.active_months
+        &self.period.active_months
     }
 
     pub fn months_sums(&self) -> &Vec<(chrono::NaiveDate, f32)> {
-        &self.months_sums
+        &self.period.months_sums
+    }
+
+    pub fn months_flows(&self) -> &[MonthFlows] {
+        &self.period.months_flows
+    }
+
+    pub fn month_anomalies(&self) -> &[MonthAnomaly] {
+        &self.period.month_anomalies
+    }
+
+    pub fn line_anomalies(&self) -> &[LineAnomaly] {
+        &self.period.line_anomalies
+    }
+
+    pub fn merchants(&self) -> &[Merchant] {
+        &self.period.merchants
+    }
+
+    pub fn daily_spending(&self) -> &[(chrono::NaiveDate, f32)] {
+        &self.period.daily_spending
+    }
+
+    /// Sort categories and sub-categories totals by the given statistic
+    pub fn sort_totals(&mut self, statistic: Statistic) {
+        // Averages depend on the average mode
+        let key = |months: &[f32], average: f32| match statistic {
+            Statistic::Average => average,
+            _ => MonthStats::new(months).sort_key(statistic),
+        };
+        self.parameters.totals_sort = statistic;
+        self.period
+            .categories_totals
+            .sort_by(|(_, a, _, a_average), (_, b, _, b_average)| {
+                key(a, *a_average).total_cmp(&key(b, *b_average))
+            });
+        self.period.sub_categories_total.sort_by(
+            |(_, _, a, _, a_average), (_, _, b, _, b_average)| {
+                key(a, *a_average).total_cmp(&key(b, *b_average))
+            },
+        );
+    }
+
+    /// Compute categories and sub-categories averages with the given mode
+    pub fn set_average_mode(&mut self, mode: AverageMode) {
+        let first_complete = self.period.lower_date.day() == 1;
+        let last_complete = (self.period.higher_date + chrono::Days::new(1)).day() == 1;
+        self.parameters.average_mode = mode;
+        for (_, months, _, average) in &mut self.period.categories_totals {
+            *average = mode.average(months, first_complete, last_complete);
+        }
+        for (_, _, months, _, average) in &mut self.period.sub_categories_total {
+            *average = mode.average(months, first_complete, last_complete);
+        }
+        self.sort_totals(self.parameters.totals_sort);
     }
 }
@@ -4,9 +4,19 @@ use app::MyApp;
 use clap::Parser;
 use eframe::egui;
 
+mod anomaly;
 mod app;
+mod breakdown;
+mod budget;
+mod calendar;
+mod compare;
 mod extract;
+mod forecast;
 mod line;
+mod merchant;
+mod recurring;
+mod savings;
+mod stats;
 
 #[derive(Parser, Debug)]
 struct Args {
@@ -25,7 +35,7 @@ fn main() -> eframe::Result<()> {
         options,
         Box::new(|cc| {
             egui_extras::install_image_loaders(&cc.egui_ctx);
-            Ok(Box::new(MyApp::new(args.lines_path.clone())))
+            Ok(Box::new(MyApp::new(cc, args.lines_path.clone())))
         }),
     )
 }
@@ -0,0 +1,159 @@
+use std::collections::HashMap;
+
+use chrono::Datelike;
+use derive_more::Constructor;
+use itertools::Itertools;
+
+use crate::{
+    extract::EXCLUDED_CATEGORY,
+    line::{Line, Lines},
+};
+
+/// Lines aggregated by payee.
+#[derive(Debug, Clone, PartialEq, Constructor)]
+pub struct Merchant {
+    payee: String,
+    /// Most frequent category of the payee lines
+    category: String,
+    total: f32,
+    count: usize,
+    first_date: chrono::NaiveDate,
+    last_date: chrono::NaiveDate,
+    /// Amounts by month of [`Lines::all_months`]
+    months: Vec<f32>,
+}
+
+impl Merchant {
+    pub fn payee(&self) -> &str {
+        &self.payee
+    }
+
+    pub fn category(&self) -> &str {
+        &self.category
+    }
+
+    pub fn total(&self) -> f32 {
+        self.total
+    }
+
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    pub fn average_ticket(&self) -> f32 {
+        self.total / self.count as f32
+    }
+
+    pub fn first_date(&self) -> chrono::NaiveDate {
+        self.first_date
+    }
+
+    pub fn last_date(&self) -> chrono::NaiveDate {
+        self.last_date
+    }
+
+    pub fn months(&self) -> &[f32] {
+        &self.months
+    }
+}
+
+/// Lines aggregated by payee, largest expenses first
+pub fn merchants(lines: &Lines) -> Vec<Merchant> {
+    let mut by_payee: HashMap<&str, Vec<&Line>> = HashMap::new();
+    for line in lines
+        .lines()
+        .iter()
+        .filter(|l| l.categorie() != EXCLUDED_CATEGORY)
+    {
+        by_payee.entry(line.payee()).or_default().push(line);
+    }
+
+    by_payee
+        .into_iter()
+        .map(|(payee, payee_lines)| {
+            let category = payee_lines
+                .iter()
+                .map(|l| l.categorie())
+                .counts()
+                .into_iter()
+                .max_by_key(|(category, count)| (*count, std::cmp::Reverse(*category)))
+                .map(|(category, _)| category.to_string())
+                .unwrap_or_default();
+            let months = lines
+                .all_months()
+                .iter()
+                .map(|month| {
+                    payee_lines
+                        .iter()
+                        .filter(|l| {
+                            l.date().year() == month.year() && l.date().month() == month.month()
+                        })
+                        .map(|l| l.amount())
+                        .sum()
+                })
+                .collect();
+
+            Merchant::new(
+                payee.to_string(),
+                category,
+                payee_lines.iter().map(|l| l.amount()).sum(),
+                payee_lines.len(),
+                payee_lines.iter().map(|l| l.date()).min().unwrap(),
+                payee_lines.iter().map(|l| l.date()).max().unwrap(),
+                months,
+            )
+        })
+        .sorted_by(|a, b| {
+            a.total()
+                .total_cmp(&b.total())
+                .then_with(|| a.payee().cmp(b.payee()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extract::TryIntoLines;
+
+    #[test]
+    fn aggregate_by_payee() {
+        // Given
+        let raw = "header
+05/01/2024;CB SUPER 0501;CB SUPER 0501;;;Carte bancaire;Alimentation;Hyper/supermarche;-60;;05/01/2024;05/01/2024;0
+20/01/2024;CB SUPER 2001;CB SUPER 2001;;;Carte bancaire;Alimentation;Hyper/supermarche;-40;;20/01/2024;20/01/2024;0
+03/02/2024;CB SUPER 0302;CB SUPER 0302;;;Carte bancaire;Alimentation;Hyper/supermarche;-50;;03/02/2024;03/02/2024;0
+10/02/2024;CINEMA;CINEMA;;;Carte bancaire;Loisirs;Cinema;-12;;10/02/2024;10/02/2024;0"
+            .to_string();
+        let lines = raw.into_lines("test".to_string()).unwrap();
+
+        // When
+        let merchants = merchants(&lines);
+
+        // Then
+        assert_eq!(
+            merchants,
+            vec![
+                Merchant::new(
+                    "SUPER".to_string(),
+                    "Alimentation".to_string(),
+                    -150.0,
+                    3,
+                    chrono::NaiveDate::from_ymd_opt(2024, 1, 5).unwrap(),
+                    chrono::NaiveDate::from_ymd_opt(2024, 2, 3).unwrap(),
+                    vec![-100.0, -50.0],
+                ),
+                Merchant::new(
+                    "CINEMA".to_string(),
+                    "Loisirs".to_string(),
+                    -12.0,
+                    1,
+                    chrono::NaiveDate::from_ymd_opt(2024, 2, 10).unwrap(),
+                    chrono::NaiveDate::from_ymd_opt(2024, 2, 10).unwrap(),
+                    vec![0.0, -12.0],
+                ),
+            ]
+        );
+        assert_eq!(merchants[0].average_ticket(), -50.0);
+    }
+}
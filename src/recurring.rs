@@ -0,0 +1,383 @@
+use derive_more::{Constructor, Display};
+use serde::{Deserialize, Serialize};
+
+use crate::{extract::approx_eq_pct_ref, line::Line};
+
+/// Label words added by the bank around the real payee name.
+const PAYEE_NOISE: &[&str] = &[
+    "CB",
+    "CARTE",
+    "NFC",
+    "FACT",
+    "PRLV",
+    "PRELEVEMENT",
+    "SEPA",
+    "VIR",
+    "VIREMENT",
+    "INST",
+    "PAIEMENT",
+    "RETRAIT",
+    "DAB",
+    "ECH",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display)]
+pub enum Periodicity {
+    #[display("Hebdomadaire")]
+    Weekly,
+    #[display("Mensuel")]
+    Monthly,
+    #[display("Trimestriel")]
+    Quarterly,
+    #[display("Annuel")]
+    Yearly,
+}
+
+impl Periodicity {
+    /// Periodicity matching the given interval between two occurrences, if any.
+    pub fn from_days(days: i64) -> Option<Self> {
+        match days {
+            5..=9 => Some(Self::Weekly),
+            26..=35 => Some(Self::Monthly),
+            80..=100 => Some(Self::Quarterly),
+            350..=380 => Some(Self::Yearly),
+            _ => None,
+        }
+    }
+
+    /// Minimum occurrences count required to consider a series of this periodicity
+    /// as recurring when it does not reach the recurring months count. Monthly
+    /// series only rely on the recurring months count.
+    pub fn min_occurrences(&self) -> Option<usize> {
+        match self {
+            Self::Weekly => Some(4),
+            Self::Monthly => None,
+            Self::Quarterly => Some(3),
+            Self::Yearly => Some(2),
+        }
+    }
+
+    /// Average occurrences count by month
+    pub fn per_month(&self) -> f32 {
+        match self {
+            Self::Weekly => 52.0 / 12.0,
+            Self::Monthly => 1.0,
+            Self::Quarterly => 1.0 / 3.0,
+            Self::Yearly => 1.0 / 12.0,
+        }
+    }
+
+    pub fn next(&self, date: chrono::NaiveDate) -> chrono::NaiveDate {
+        match self {
+            Self::Weekly => date + chrono::Days::new(7),
+            Self::Monthly => date + chrono::Months::new(1),
+            Self::Quarterly => date + chrono::Months::new(3),
+            Self::Yearly => date + chrono::Months::new(12),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Constructor)]
+pub struct RecurringSeries {
+    payee: String,
+    line: Line,
+    occurrences: Vec<Line>,
+    periodicity: Periodicity,
+    next_date: chrono::NaiveDate,
+}
+
+impl RecurringSeries {
+    pub fn payee(&self) -> &str {
+        &self.payee
+    }
+
+    pub fn line(&self) -> &Line {
+        &self.line
+    }
+
+    pub fn occurrences(&self) -> &[Line] {
+        &self.occurrences
+    }
+
+    pub fn periodicity(&self) -> Periodicity {
+        self.periodicity
+    }
+
+    pub fn next_date(&self) -> chrono::NaiveDate {
+        self.next_date
+    }
+
+    pub fn first_date(&self) -> chrono::NaiveDate {
+        self.occurrences.first().unwrap().date()
+    }
+
+    pub fn last_date(&self) -> chrono::NaiveDate {
+        self.occurrences.last().unwrap().date()
+    }
+
+    pub fn average_amount(&self) -> f32 {
+        self.occurrences.iter().map(|l| l.amount()).sum::<f32>() / self.occurrences.len() as f32
+    }
+
+    /// Average amount spread by month according to periodicity
+    pub fn monthly_cost(&self) -> f32 {
+        self.average_amount() * self.periodicity.per_month()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Constructor)]
+pub struct PriceChange {
+    payee: String,
+    periodicity: Periodicity,
+    date: chrono::NaiveDate,
+    old_amount: f32,
+    new_amount: f32,
+}
+
+impl PriceChange {
+    pub fn payee(&self) -> &str {
+        &self.payee
+    }
+
+    pub fn date(&self) -> chrono::NaiveDate {
+        self.date
+    }
+
+    pub fn old_amount(&self) -> f32 {
+        self.old_amount
+    }
+
+    pub fn new_amount(&self) -> f32 {
+        self.new_amount
+    }
+
+    /// Amount variation (in percent), positive when the absolute amount raised
+    pub fn variation(&self) -> f32 {
+        (self.new_amount.abs() - self.old_amount.abs()) / self.old_amount.abs() * 100.0
+    }
+
+    /// Amount difference over a year, negative when it costs more
+    pub fn yearly_impact(&self) -> f32 {
+        (self.new_amount - self.old_amount) * self.periodicity.per_month() * 12.0
+    }
+}
+
+/// Expected occurrence of a recurring series not found in imported lines.
+#[derive(Debug, Clone, PartialEq, Constructor)]
+pub struct MissedPayment {
+    payee: String,
+    periodicity: Periodicity,
+    expected_date: chrono::NaiveDate,
+    expected_amount: f32,
+}
+
+impl MissedPayment {
+    pub fn payee(&self) -> &str {
+        &self.payee
+    }
+
+    pub fn periodicity(&self) -> Periodicity {
+        self.periodicity
+    }
+
+    pub fn expected_date(&self) -> chrono::NaiveDate {
+        self.expected_date
+    }
+
+    pub fn expected_amount(&self) -> f32 {
+        self.expected_amount
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Decision {
+    /// Always recurring, whatever detection settings
+    Pinned,
+    /// Never recurring
+    Dismissed,
+}
+
+/// User decision about a recurring series, identified by its payee and amount.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Constructor)]
+pub struct RecurringDecision {
+    payee: String,
+    amount: f32,
+    decision: Decision,
+}
+
+impl RecurringDecision {
+    pub fn payee(&self) -> &str {
+        &self.payee
+    }
+
+    pub fn amount(&self) -> f32 {
+        self.amount
+    }
+
+    pub fn decision(&self) -> Decision {
+        self.decision
+    }
+
+    pub fn matches_payee(&self, payee: &str, similarity: f32) -> bool {
+        payee_similarity(&self.payee, payee) >= similarity
+    }
+
+    pub fn matches_amount(&self, amount: f32, approx: f32) -> bool {
+        approx_eq_pct_ref(self.amount, amount, approx)
+    }
+}
+
+/// Median of intervals (in days) between successive distinct dates.
+pub fn median_gap(dates: &[chrono::NaiveDate]) -> Option<i64> {
+    let mut gaps = dates
+        .windows(2)
+        .map(|w| (w[1] - w[0]).num_days())
+        .filter(|d| *d > 0)
+        .collect::<Vec<i64>>();
+    if gaps.is_empty() {
+        return None;
+    }
+
+    gaps.sort();
+    let middle = gaps.len() / 2;
+    if gaps.len() % 2 == 0 {
+        Some((gaps[middle - 1] + gaps[middle]) / 2)
+    } else {
+        Some(gaps[middle])
+    }
+}
+
+/// Word made of digits and date separators only (like "200325" or "12/03"), or with
+/// at least 4 consecutive digits (like a reference number).
+fn is_date_or_reference(word: &str) -> bool {
+    let date = word.chars().any(|c| c.is_ascii_digit())
+        && word
+            .chars()
+            .all(|c| c.is_ascii_digit() || matches!(c, '/' | '.' | '-' | ':'));
+    let reference = word
+        .split(|c: char| !c.is_ascii_digit())
+        .any(|digits| digits.len() >= 4);
+    date || reference
+}
+
+/// Payee name without card/transfer prefixes, dates and reference numbers.
+pub fn normalize_payee(label: &str) -> String {
+    let words = label
+        .split_whitespace()
+        .map(|w| {
+            w.trim_matches(|c: char| !c.is_alphanumeric())
+                .to_uppercase()
+        })
+        .filter(|w| !w.is_empty() && !is_date_or_reference(w) && !PAYEE_NOISE.contains(&w.as_str()))
+        .collect::<Vec<String>>();
+
+    if words.is_empty() {
+        return label.trim().to_uppercase();
+    }
+
+    words.join(" ")
+}
+
+/// Share of words of the shortest payee found in the other one (from 0.0 to 1.0).
+pub fn payee_similarity(a: &str, b: &str) -> f32 {
+    words_similarity(
+        &a.split(' ').collect::<Vec<&str>>(),
+        &b.split(' ').collect::<Vec<&str>>(),
+    )
+}
+
+/// [`payee_similarity`] of payees already split into words.
+pub fn words_similarity(a: &[&str], b: &[&str]) -> f32 {
+    if a == b {
+        return 1.0;
+    }
+
+    let (shortest, longest) = if a.len() <= b.len() { (a, b) } else { (b, a) };
+    let common = shortest.iter().filter(|w| longest.contains(w)).count();
+
+    common as f32 / shortest.len() as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> chrono::NaiveDate {
+        chrono::NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn periodicity_from_median_gap() {
+        let weekly = [date(2025, 3, 1), date(2025, 3, 8), date(2025, 3, 14)];
+        let quarterly = [date(2025, 1, 10), date(2025, 4, 10), date(2025, 7, 11)];
+        let yearly = [date(2024, 2, 5), date(2025, 2, 3)];
+        let single = [date(2024, 2, 5)];
+
+        assert_eq!(
+            median_gap(&weekly).and_then(Periodicity::from_days),
+            Some(Periodicity::Weekly)
+        );
+        assert_eq!(
+            median_gap(&quarterly).and_then(Periodicity::from_days),
+            Some(Periodicity::Quarterly)
+        );
+        assert_eq!(
+            median_gap(&yearly).and_then(Periodicity::from_days),
+            Some(Periodicity::Yearly)
+        );
+        assert_eq!(median_gap(&single), None);
+    }
+
+    #[test]
+    fn series_amounts() {
+        // Given
+        let line = |date: &str, debit: f32| {
+            Line::new(
+                date.to_string(),
+                "EAU".to_string(),
+                "PRLV SEPA EAU".to_string(),
+                "".to_string(),
+                "Logement".to_string(),
+                "Eau".to_string(),
+                Some(debit),
+                None,
+            )
+        };
+        let occurrences = vec![
+            line("15/01/2024", -42.0),
+            line("15/04/2024", -45.0),
+            line("15/07/2024", -48.0),
+        ];
+
+        // When
+        let series = RecurringSeries::new(
+            "EAU".to_string(),
+            occurrences[0].clone(),
+            occurrences,
+            Periodicity::Quarterly,
+            date(2024, 10, 15),
+        );
+
+        // Then
+        assert_eq!(series.first_date(), date(2024, 1, 15));
+        assert_eq!(series.last_date(), date(2024, 7, 15));
+        assert_eq!(series.average_amount(), -45.0);
+        assert_eq!(series.monthly_cost(), -15.0);
+    }
+
+    #[test]
+    fn normalize_and_compare_payees() {
+        assert_eq!(normalize_payee("CB AREA NFC FACT 200325"), "AREA");
+        assert_eq!(normalize_payee("PRLV SEPA EDF"), "EDF");
+        assert_eq!(normalize_payee("PRLV SEPA EDF CLIENTS"), "EDF CLIENTS");
+        assert_eq!(normalize_payee("PAYPAL *NETFLIX 12/03"), "PAYPAL NETFLIX");
+        assert_eq!(normalize_payee("2508085IN0103420"), "2508085IN0103420");
+        assert_eq!(normalize_payee("CB O2SWITCH FACT 010724"), "O2SWITCH");
+        assert_eq!(normalize_payee("CB MAG12 FACT 200325 12.03.25"), "MAG12");
+        assert_eq!(normalize_payee("VIR 200325"), "VIR 200325");
+
+        assert_eq!(payee_similarity("EDF", "EDF CLIENTS"), 1.0);
+        assert_eq!(payee_similarity("EDF CLIENTS", "ENGIE CLIENTS"), 0.5);
+        assert_eq!(payee_similarity("AREA", "INTERMARCHE"), 0.0);
+    }
+}
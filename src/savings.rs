@@ -0,0 +1,79 @@
+use derive_more::Constructor;
+
+/// Income and expenses of a month, excluding internal transfers.
+#[derive(Debug, Clone, PartialEq, Constructor)]
+pub struct MonthFlows {
+    month: chrono::NaiveDate,
+    income: f32,
+    expenses: f32,
+}
+
+impl MonthFlows {
+    pub fn month(&self) -> chrono::NaiveDate {
+        self.month
+    }
+
+    pub fn income(&self) -> f32 {
+        self.income
+    }
+
+    pub fn expenses(&self) -> f32 {
+        self.expenses
+    }
+
+    pub fn net(&self) -> f32 {
+        self.income + self.expenses
+    }
+
+    /// Share of income saved (in percent), if there is income
+    pub fn savings_rate(&self) -> Option<f32> {
+        savings_rate(self.net(), self.income)
+    }
+}
+
+fn savings_rate(net: f32, income: f32) -> Option<f32> {
+    (income > 0.0).then(|| net / income * 100.0)
+}
+
+/// Savings rate of each month over the `window` months ending with it, once
+/// enough months are available.
+pub fn rolling_savings_rate(flows: &[MonthFlows], window: usize) -> Vec<Option<f32>> {
+    (0..flows.len())
+        .map(|i| {
+            if i + 1 < window {
+                return None;
+            }
+            let months = &flows[i + 1 - window..=i];
+            savings_rate(
+                months.iter().map(|f| f.net()).sum(),
+                months.iter().map(|f| f.income()).sum(),
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn savings_rates() {
+        // Given
+        let month = |m| chrono::NaiveDate::from_ymd_opt(2024, m, 1).unwrap();
+        let flows = vec![
+            MonthFlows::new(month(1), 2000.0, -1500.0),
+            MonthFlows::new(month(2), 2000.0, -2500.0),
+            MonthFlows::new(month(3), 0.0, -250.0),
+        ];
+
+        // When
+        let rolling = rolling_savings_rate(&flows, 2);
+
+        // Then
+        assert_eq!(flows[0].net(), 500.0);
+        assert_eq!(flows[0].savings_rate(), Some(25.0));
+        assert_eq!(flows[1].savings_rate(), Some(-25.0));
+        assert_eq!(flows[2].savings_rate(), None);
+        assert_eq!(rolling, vec![None, Some(0.0), Some(-37.5)]);
+    }
+}
@@ -0,0 +1,215 @@
+use derive_more::Display;
+use itertools::Itertools;
+
+pub fn median(values: &[f32]) -> f32 {
+    if values.is_empty() {
+        return 0.0;
+    }
+
+    let sorted = values
+        .iter()
+        .copied()
+        .sorted_by(|a, b| a.total_cmp(b))
+        .collect::<Vec<f32>>();
+    let middle = sorted.len() / 2;
+    if sorted.len() % 2 == 0 {
+        (sorted[middle - 1] + sorted[middle]) / 2.0
+    } else {
+        sorted[middle]
+    }
+}
+
+/// Statistics of monthly amounts.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MonthStats {
+    total: f32,
+    average: f32,
+    median: f32,
+    min: f32,
+    max: f32,
+    std_dev: f32,
+    active_months: usize,
+}
+
+impl MonthStats {
+    pub fn new(months: &[f32]) -> Self {
+        let total = months.iter().sum::<f32>();
+        let average = if months.is_empty() {
+            0.0
+        } else {
+            total / months.len() as f32
+        };
+        let variance = if months.is_empty() {
+            0.0
+        } else {
+            months.iter().map(|v| (v - average).powi(2)).sum::<f32>() / months.len() as f32
+        };
+
+        Self {
+            total,
+            average,
+            median: median(months),
+            min: months.iter().copied().reduce(f32::min).unwrap_or_default(),
+            max: months.iter().copied().reduce(f32::max).unwrap_or_default(),
+            std_dev: variance.sqrt(),
+            active_months: months.iter().filter(|v| **v != 0.0).count(),
+        }
+    }
+
+    pub fn median(&self) -> f32 {
+        self.median
+    }
+
+    pub fn min(&self) -> f32 {
+        self.min
+    }
+
+    pub fn max(&self) -> f32 {
+        self.max
+    }
+
+    pub fn std_dev(&self) -> f32 {
+        self.std_dev
+    }
+
+    pub fn active_months(&self) -> usize {
+        self.active_months
+    }
+
+    /// Value of the given statistic, ordered so that sorting ascending gives the
+    /// most significant first
+    pub fn sort_key(&self, statistic: Statistic) -> f32 {
+        match statistic {
+            Statistic::Total => self.total,
+            Statistic::Average => self.average,
+            Statistic::Median => self.median,
+            Statistic::Min => self.min,
+            Statistic::Max => -self.max,
+            Statistic::StdDev => -self.std_dev,
+            Statistic::ActiveMonths => -(self.active_months as f32),
+        }
+    }
+}
+
+/// Statistic used to sort categories totals
+#[derive(Debug, Clone, Copy, PartialEq, Default, Display)]
+pub enum Statistic {
+    #[default]
+    #[display("Total")]
+    Total,
+    #[display("Moyenne")]
+    Average,
+    #[display("Médiane")]
+    Median,
+    #[display("Min")]
+    Min,
+    #[display("Max")]
+    Max,
+    #[display("Écart type")]
+    StdDev,
+    #[display("Mois actifs")]
+    ActiveMonths,
+}
+
+impl Statistic {
+    pub const ALL: [Statistic; 7] = [
+        Statistic::Total,
+        Statistic::Average,
+        Statistic::Median,
+        Statistic::Min,
+        Statistic::Max,
+        Statistic::StdDev,
+        Statistic::ActiveMonths,
+    ];
+}
+
+/// Months counted to compute monthly averages
+#[derive(Debug, Clone, Copy, PartialEq, Default, Display)]
+pub enum AverageMode {
+    /// All months of the period, even partial first and last ones
+    #[default]
+    #[display("tous les mois")]
+    AllMonths,
+    /// Months entirely covered by the period
+    #[display("mois complets")]
+    CompleteMonths,
+    /// Months with at least one line
+    #[display("mois actifs")]
+    ActiveMonths,
+    #[display("{_0} mois")]
+    Custom(usize),
+}
+
+impl AverageMode {
+    /// Monthly average of the given amounts by month, knowing if the first and
+    /// last months are entirely covered by the period
+    pub fn average(&self, months: &[f32], first_complete: bool, last_complete: bool) -> f32 {
+        let (values, count) = match self {
+            AverageMode::AllMonths => (months, months.len()),
+            AverageMode::CompleteMonths => {
+                let start = usize::from(!first_complete).min(months.len());
+                let end = (months.len() - usize::from(!last_complete)).max(start);
+                (&months[start..end], end - start)
+            }
+            AverageMode::ActiveMonths => (months, months.iter().filter(|v| **v != 0.0).count()),
+            AverageMode::Custom(count) => (months, *count),
+        };
+
+        if count == 0 {
+            0.0
+        } else {
+            values.iter().sum::<f32>() / count as f32
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn month_stats() {
+        // Given
+        let months = [-100.0, 0.0, -40.0, -60.0];
+
+        // When
+        let stats = MonthStats::new(&months);
+
+        // Then
+        assert_eq!(stats.median(), -50.0);
+        assert_eq!(stats.min(), -100.0);
+        assert_eq!(stats.max(), 0.0);
+        assert_eq!(stats.std_dev(), 1300.0_f32.sqrt());
+        assert_eq!(stats.active_months(), 3);
+        assert_eq!(stats.sort_key(Statistic::Average), -50.0);
+    }
+
+    #[test]
+    fn average_modes() {
+        // Given
+        let months = [-30.0, -100.0, 0.0, -200.0, -50.0];
+
+        // When/Then
+        assert_eq!(AverageMode::AllMonths.average(&months, false, false), -76.0);
+        assert_eq!(
+            AverageMode::CompleteMonths.average(&months, false, false),
+            -100.0
+        );
+        assert_eq!(
+            AverageMode::CompleteMonths.average(&months, true, false),
+            -82.5
+        );
+        assert_eq!(
+            AverageMode::ActiveMonths.average(&months, false, false),
+            -95.0
+        );
+        assert_eq!(
+            AverageMode::Custom(19).average(&months, false, false),
+            -20.0
+        );
+        assert_eq!(
+            AverageMode::CompleteMonths.average(&[-10.0], false, false),
+            0.0
+        );
+    }
+}